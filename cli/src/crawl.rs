@@ -4,6 +4,7 @@ use clap::{
     error::{ContextKind, ContextValue, ErrorKind},
     Error,
 };
+use polymath_crawler::Crawler;
 use std::{fs, path::PathBuf};
 
 /// Max depth value allowed on cli.
@@ -12,7 +13,7 @@ const MAX_DEPTH: usize = 100;
 pub fn handler(
     url: url::Url,
     depth: usize,
-    _robots_txt: bool,
+    robots_txt: bool,
     path: Option<PathBuf>,
 ) -> Result<(), clap::error::Error> {
     if depth > MAX_DEPTH {
@@ -37,7 +38,7 @@ pub fn handler(
         "Crawling {} with a maximum of {} crawled pages.",
         url, depth
     );
-    
+
     if let Some(path) = path {
         println!(
             "Saving pages result on {}",
@@ -47,5 +48,15 @@ pub fn handler(
         fs::create_dir(path)?;
     }
 
-    Ok(())
+    let mut crawler = Crawler::new().depth(depth);
+    if robots_txt {
+        let user_agent = format!("polymath/{}", env!("CARGO_PKG_VERSION"));
+        crawler = crawler
+            .user_agent(user_agent.clone())
+            .register_event(Box::new(robots::Extension::new(&user_agent)));
+    }
+
+    crawler
+        .fetch(url.to_string())
+        .map_err(|error| Error::raw(ErrorKind::Io, format!("{}\n", error)))
 }
@@ -2,7 +2,7 @@
 
 mod crawl;
 
-use clap::{command, Parser as _};
+use clap::Parser as _;
 use clap_derive::{Parser, Subcommand};
 use std::path::PathBuf;
 use url::Url;
@@ -26,7 +26,7 @@ enum Commands {
         #[arg(long)]
         robots_txt: Option<bool>,
         /// Directory for saving HTML content of pages.
-        /// 
+        ///
         /// If not set, do not save anything.
         #[arg(short, long)]
         path: Option<PathBuf>,
@@ -26,6 +26,11 @@ impl<K: Eq + std::hash::Hash + Clone, V> LRUCache<K, V> {
         self.map.len()
     }
 
+    /// Whether the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
     /// Returns the value of a key.
     pub fn get(&mut self, key: &K) -> Option<&V> {
         if self.map.contains_key(key) {
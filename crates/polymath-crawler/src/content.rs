@@ -0,0 +1,198 @@
+//! Selection of the resources to fetch, from their URL extension and
+//! `Content-Type`.
+//!
+//! HTML pages are always fetched. Other resources are only fetched if their
+//! extension is one of the [allowed extensions](crate::Crawler::allowed_extensions).
+
+use url::Url;
+
+/// Extensions of URLs serving HTML pages.
+const PAGE_EXT: [&str; 9] = [
+    "html", "htm", "xhtml", "shtml", "php", "asp", "aspx", "jsp", "cgi",
+];
+
+/// Media types of HTML pages.
+const PAGE_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
+/// Media types of text resources outside of `text/*`.
+const TEXT_TYPES: [&str; 3] = [
+    "application/json",
+    "application/javascript",
+    "application/xml",
+];
+
+/// Media types of the file extensions the crawler knows.
+const MEDIA_TYPES: [(&str, &str); 33] = [
+    ("pdf", "application/pdf"),
+    ("ppt", "application/vnd.ms-powerpoint"),
+    (
+        "pptx",
+        "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    ),
+    ("doc", "application/msword"),
+    (
+        "docx",
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    ),
+    ("odp", "application/vnd.oasis.opendocument.presentation"),
+    ("odt", "application/vnd.oasis.opendocument.text"),
+    ("tex", "application/x-tex"),
+    ("tex", "text/x-tex"),
+    ("txt", "text/plain"),
+    ("jpeg", "image/jpeg"),
+    ("jpg", "image/jpeg"),
+    ("png", "image/png"),
+    ("webp", "image/webp"),
+    ("gif", "image/gif"),
+    ("svg", "image/svg+xml"),
+    ("ico", "image/x-icon"),
+    ("mp4", "video/mp4"),
+    ("ogv", "video/ogg"),
+    ("mov", "video/quicktime"),
+    ("webm", "video/webm"),
+    ("mp3", "audio/mpeg"),
+    ("ogg", "audio/ogg"),
+    ("css", "text/css"),
+    ("js", "text/javascript"),
+    ("js", "application/javascript"),
+    ("json", "application/json"),
+    ("xml", "application/xml"),
+    ("zip", "application/zip"),
+    ("gz", "application/gzip"),
+    ("woff", "font/woff"),
+    ("woff2", "font/woff2"),
+    ("ttf", "font/ttf"),
+];
+
+/// Lowercase extension of the last segment of a URL path, if any.
+pub(crate) fn extension(url: &Url) -> Option<String> {
+    let segment = url.path_segments()?.next_back()?;
+    let (_, extension) = segment.rsplit_once('.')?;
+
+    (!extension.is_empty()).then(|| extension.to_lowercase())
+}
+
+/// Whether a media type is the one of an HTML page.
+pub(crate) fn is_page_type(media_type: &str) -> bool {
+    PAGE_TYPES.contains(&media_type)
+}
+
+/// Whether a media type is the one of a text resource, whose body must be
+/// decoded.
+pub(crate) fn is_text_type(media_type: &str) -> bool {
+    media_type.starts_with("text/") ||
+        media_type.ends_with("+xml") ||
+        media_type.ends_with("+json") ||
+        TEXT_TYPES.contains(&media_type)
+}
+
+/// Checks the extension of a URL before fetching it. Returns the extension
+/// if it is known to serve something else than an HTML page and is not
+/// allowed.
+///
+/// URLs without extension or with an unknown one are fetched, their
+/// `Content-Type` tells what they are.
+pub(crate) fn disallowed_extension(
+    url: &Url,
+    allowed: &[String],
+) -> Option<String> {
+    let extension = extension(url)?;
+
+    let known = MEDIA_TYPES.iter().any(|(ext, _)| *ext == extension);
+    let allowed = allowed
+        .iter()
+        .any(|ext| ext.eq_ignore_ascii_case(&extension));
+
+    (known && !allowed && !PAGE_EXT.contains(&extension.as_str()))
+        .then_some(extension)
+}
+
+/// Whether a response with a media type should be read.
+///
+/// Generic binary responses are read if the URL extension is allowed, as
+/// some servers send every file as `application/octet-stream`.
+pub(crate) fn is_allowed_type(
+    media_type: &str,
+    url: &Url,
+    allowed: &[String],
+) -> bool {
+    let is_allowed = |extension: &str| {
+        allowed
+            .iter()
+            .any(|ext| ext.eq_ignore_ascii_case(extension))
+    };
+
+    if is_page_type(media_type) {
+        return true;
+    }
+    if media_type == "application/octet-stream" {
+        return extension(url).is_some_and(|extension| is_allowed(&extension));
+    }
+
+    MEDIA_TYPES
+        .iter()
+        .any(|(extension, known)| *known == media_type && is_allowed(extension))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(extension(&url("https://gravitalia.com/")), None);
+        assert_eq!(extension(&url("https://gravitalia.com/a.b/c")), None);
+        assert_eq!(
+            extension(&url("https://gravitalia.com/CV.PDF?v=2")),
+            Some("pdf".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_disallowed_extension() {
+        let allowed = vec!["pdf".to_owned()];
+
+        assert_eq!(
+            disallowed_extension(
+                &url("https://gravitalia.com/a.png"),
+                &allowed
+            ),
+            Some("png".to_owned())
+        );
+        for allowed_url in [
+            "https://gravitalia.com/a.pdf",
+            "https://gravitalia.com/index.php",
+            "https://gravitalia.com/about",
+            "https://gravitalia.com/users/john.doe",
+        ] {
+            assert_eq!(disallowed_extension(&url(allowed_url), &allowed), None);
+        }
+    }
+
+    #[test]
+    fn test_is_allowed_type() {
+        let allowed = vec!["pdf".to_owned(), "jpg".to_owned()];
+        let page = url("https://gravitalia.com/file");
+
+        assert!(is_allowed_type("text/html", &page, &allowed));
+        assert!(is_allowed_type("application/pdf", &page, &allowed));
+        assert!(is_allowed_type("image/jpeg", &page, &allowed));
+        assert!(!is_allowed_type("image/png", &page, &allowed));
+        assert!(!is_allowed_type("application/x-unknown", &page, &allowed));
+
+        assert!(!is_allowed_type(
+            "application/octet-stream",
+            &page,
+            &allowed
+        ));
+        assert!(is_allowed_type(
+            "application/octet-stream",
+            &url("https://gravitalia.com/file.pdf"),
+            &allowed
+        ));
+    }
+}
@@ -0,0 +1,173 @@
+//! Detection of the character encoding of text responses.
+//!
+//! The encoding is read, in order, from the `charset` parameter of the
+//! `Content-Type` header, the byte order mark, the `<meta charset>` or
+//! `<meta http-equiv="Content-Type">` tags of HTML pages and, as a last
+//! resort, guessed from the body.
+
+use std::sync::LazyLock;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use regex_lite::Regex;
+
+/// Number of bytes of an HTML page scanned for a `<meta>` charset.
+const PRESCAN_SIZE: usize = 1024;
+
+/// `charset` declared by a `<meta>` tag, either as its `charset` attribute
+/// or in its `content` attribute.
+static META_CHARSET: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r#"(?i)<meta\s[^>]*charset\s*=\s*["']?\s*([\w.:-]+)"#).unwrap()
+});
+
+/// Encoding of a `charset` parameter in a `Content-Type` header value.
+fn from_header(content_type: &str) -> Option<&'static Encoding> {
+    content_type.split(';').skip(1).find_map(|parameter| {
+        let (name, value) = parameter.split_once('=')?;
+
+        name.trim()
+            .eq_ignore_ascii_case("charset")
+            .then(|| value.trim().trim_matches(['"', '\'']))
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+    })
+}
+
+/// Encoding declared by the `<meta>` tags at the start of an HTML page.
+fn from_meta(body: &[u8]) -> Option<&'static Encoding> {
+    let start = String::from_utf8_lossy(&body[..body.len().min(PRESCAN_SIZE)]);
+    let label = META_CHARSET.captures(&start)?.get(1)?.as_str();
+
+    // A page able to declare its encoding in ASCII is not UTF-16.
+    Encoding::for_label(label.as_bytes()).map(|encoding| {
+        if encoding == UTF_16BE || encoding == UTF_16LE {
+            UTF_8
+        } else {
+            encoding
+        }
+    })
+}
+
+/// Guess of the encoding of a body without declared encoding. `tld` is the
+/// top-level domain of the page, a hint for legacy encodings.
+fn sniff(body: &[u8], tld: Option<&str>) -> &'static Encoding {
+    if std::str::from_utf8(body).is_ok() {
+        return UTF_8;
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(body, true);
+    detector.guess(tld.map(str::as_bytes), true)
+}
+
+/// Detects the encoding of a text response. `content_type` is the raw
+/// `Content-Type` header value, if any.
+pub(crate) fn detect(
+    content_type: Option<&str>,
+    body: &[u8],
+    is_html: bool,
+    tld: Option<&str>,
+) -> &'static Encoding {
+    content_type
+        .and_then(from_header)
+        .or_else(|| Encoding::for_bom(body).map(|(encoding, _)| encoding))
+        .or_else(|| is_html.then(|| from_meta(body)).flatten())
+        .unwrap_or_else(|| sniff(body, tld))
+}
+
+/// Decodes a body to UTF-8, removing its byte order mark. Malformed
+/// sequences are replaced.
+pub(crate) fn decode<'a>(
+    body: &'a [u8],
+    encoding: &'static Encoding,
+) -> std::borrow::Cow<'a, str> {
+    encoding.decode_with_bom_removal(body).0
+}
+
+#[cfg(test)]
+mod tests {
+    use encoding_rs::{GBK, SHIFT_JIS, WINDOWS_1252};
+
+    use super::*;
+
+    #[test]
+    fn test_header() {
+        assert_eq!(
+            detect(Some("text/html; charset=ISO-8859-1"), b"", true, None),
+            WINDOWS_1252
+        );
+        assert_eq!(
+            detect(Some("text/html;charset=\"shift_jis\""), b"", true, None),
+            SHIFT_JIS
+        );
+        // The header wins over the page.
+        assert_eq!(
+            detect(
+                Some("text/html; charset=gb2312"),
+                b"<meta charset=\"utf-8\">",
+                true,
+                None
+            ),
+            GBK
+        );
+    }
+
+    #[test]
+    fn test_bom() {
+        assert_eq!(
+            detect(Some("text/html"), b"\xff\xfe<\x00", true, None),
+            UTF_16LE
+        );
+        assert_eq!(
+            detect(None, b"\xef\xbb\xbf<meta charset=latin1>", true, None),
+            UTF_8
+        );
+    }
+
+    #[test]
+    fn test_meta() {
+        assert_eq!(
+            detect(None, b"<html><meta charset='Shift_JIS'>", true, None),
+            SHIFT_JIS
+        );
+        assert_eq!(
+            detect(
+                None,
+                b"<meta http-equiv=\"Content-Type\" \
+                content=\"text/html; charset=windows-1252\">\xe9",
+                true,
+                None
+            ),
+            WINDOWS_1252
+        );
+        assert_eq!(detect(None, b"<meta charset=utf-16>", true, None), UTF_8);
+        // Only HTML pages declare their encoding in meta tags.
+        assert_eq!(
+            detect(None, b"<meta charset=\"shift_jis\">", false, None),
+            UTF_8
+        );
+    }
+
+    #[test]
+    fn test_sniff() {
+        let (body, _, _) = SHIFT_JIS.encode(
+            "<p>日本語のページです。文字コードは宣言されていません。</p>",
+        );
+        assert_eq!(detect(None, &body, true, Some("jp")), SHIFT_JIS);
+
+        let (body, _, _) =
+            GBK.encode("<p>这是一个没有声明字符编码的中文网页。</p>");
+        assert_eq!(detect(None, &body, true, Some("cn")), GBK);
+
+        assert_eq!(
+            detect(None, b"<p>Caf\xe9 cr\xe8me</p>", true, Some("fr")),
+            WINDOWS_1252
+        );
+        assert_eq!(detect(None, "Café".as_bytes(), true, None), UTF_8);
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode(b"\xef\xbb\xbfCaf\xc3\xa9", UTF_8), "Café");
+        assert_eq!(decode(b"Caf\xe9", WINDOWS_1252), "Café");
+    }
+}
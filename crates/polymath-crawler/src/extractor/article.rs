@@ -0,0 +1,975 @@
+//! Main content extraction, in the manner of
+//! [Readability](https://github.com/mozilla/readability).
+//!
+//! Blocks of text are scored from their length and punctuation, and their
+//! scores flow up to their ancestors. The ancestor with the best score, once
+//! weighted by its link density, holds the main content, along with the
+//! siblings scoring close to it. Scripts, navigation, forms and blocks whose
+//! class or id looks like boilerplate are left out.
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use ego_tree::iter::Edge;
+use ego_tree::{NodeId, NodeRef};
+use regex_lite::Regex;
+use scraper::node::Element;
+use scraper::{Html, Node};
+use url::Url;
+
+/// Minimum number of characters of a block of text to be scored.
+const MIN_BLOCK_LENGTH: usize = 25;
+
+/// Number of ancestors a block of text passes its score to.
+const SCORED_ANCESTORS: usize = 5;
+
+/// Elements never part of the main content.
+const IGNORED_TAGS: [&str; 17] = [
+    "script", "style", "noscript", "template", "nav", "footer", "aside",
+    "form", "iframe", "svg", "button", "select", "input", "textarea", "object",
+    "embed", "canvas",
+];
+
+/// [Roles](https://developer.mozilla.org/en-US/docs/Web/Accessibility/ARIA/Roles)
+/// of elements never part of the main content.
+const IGNORED_ROLES: [&str; 7] = [
+    "banner",
+    "complementary",
+    "contentinfo",
+    "dialog",
+    "menu",
+    "navigation",
+    "search",
+];
+
+/// Elements starting a new block of text.
+const BLOCK_TAGS: [&str; 29] = [
+    "address",
+    "article",
+    "blockquote",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "table",
+    "td",
+    "th",
+    "tr",
+    "ul",
+    "caption",
+];
+
+/// Elements kept in the [cleaned HTML](Article::html). Others are replaced
+/// by their content.
+const KEPT_TAGS: [&str; 42] = [
+    "a",
+    "abbr",
+    "b",
+    "blockquote",
+    "br",
+    "caption",
+    "cite",
+    "code",
+    "dd",
+    "del",
+    "dl",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "hr",
+    "i",
+    "img",
+    "ins",
+    "li",
+    "mark",
+    "ol",
+    "p",
+    "pre",
+    "q",
+    "s",
+    "small",
+    "strong",
+    "sub",
+    "sup",
+    "table",
+    "tbody",
+    "td",
+    "th",
+    "tr",
+    "ul",
+];
+
+/// Attributes kept in the [cleaned HTML](Article::html).
+const KEPT_ATTRIBUTES: [&str; 6] =
+    ["alt", "title", "colspan", "rowspan", "datetime", "lang"];
+
+/// Elements removed from the main content when they look like boilerplate.
+const CLEANED_TAGS: [&str; 6] = ["div", "section", "ul", "ol", "table", "dl"];
+
+/// Elements without content.
+const VOID_TAGS: [&str; 3] = ["br", "hr", "img"];
+
+/// Classes and ids of boilerplate blocks.
+static UNLIKELY: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)ad-break|agegate|banner|breadcrumb|combx|comment|community|cookie|disqus|extra|footer|header|legends|menu|modal|newsletter|pager|pagination|popup|promo|related|remark|replies|rss|share|shoutbox|sidebar|skyscraper|social|sponsor|subscribe",
+    )
+    .unwrap()
+});
+
+/// Classes and ids of blocks which may hold the content even if they match
+/// [UNLIKELY].
+static MAYBE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)and|article|body|column|content|main|shadow").unwrap()
+});
+
+/// Classes and ids of blocks likely holding the content.
+static POSITIVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)article|body|content|entry|hentry|h-entry|main|page|post|text|blog|story",
+    )
+    .unwrap()
+});
+
+/// Classes and ids of blocks unlikely to hold the content.
+static NEGATIVE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"(?i)-ad-|hidden|banner|combx|comment|com-|contact|foot|footnote|masthead|media|meta|outbrain|promo|related|scroll|share|shoutbox|sidebar|skyscraper|sponsor|shopping|tags|tool|widget",
+    )
+    .unwrap()
+});
+
+/// Heading of the main content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Heading {
+    /// Level of the heading, from 1 (`<h1>`) to 6 (`<h6>`).
+    pub level: u8,
+    /// Whitespace-normalized text of the heading.
+    pub text: String,
+}
+
+/// Main content of an HTML page, without its navigation, footers, ads and
+/// scripts.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Article {
+    /// Text of the content, one block (such as a paragraph or a heading) per
+    /// line block.
+    pub text: String,
+    /// Headings of the content, in order.
+    pub headings: Vec<Heading>,
+    /// Content as an HTML fragment, keeping only structural and text-level
+    /// elements. URLs are absolute.
+    pub html: String,
+    /// Number of words of the [text](Article::text). Each Chinese or
+    /// Japanese character counts as a word.
+    pub word_count: usize,
+}
+
+/// Extracts the main content of an HTML page. `None` if no block of text is
+/// long enough to be the content.
+///
+/// Relative URLs are resolved against `url`.
+pub fn extract_article(body: &str, url: &Url) -> Option<Article> {
+    article(&Html::parse_document(body), url)
+}
+
+/// Extracts the main content of a parsed HTML page, see [extract_article].
+pub(crate) fn article(document: &Html, url: &Url) -> Option<Article> {
+    let root = document.tree.root();
+
+    let blocks = collect_blocks(root);
+    let lengths = Lengths::new(root);
+
+    let mut scores: HashMap<NodeId, f64> = HashMap::new();
+    for block in blocks {
+        let text = text_of(block);
+        let length = text.chars().count();
+        if length < MIN_BLOCK_LENGTH {
+            continue;
+        }
+
+        let score = 1.0 +
+            text.matches([',', '，', '、']).count() as f64 +
+            (length / 100).min(3) as f64;
+        for (level, ancestor) in
+            block.ancestors().take(SCORED_ANCESTORS).enumerate()
+        {
+            let Node::Element(element) = ancestor.value() else {
+                break;
+            };
+            let divider = match level {
+                0 => 1.0,
+                1 => 2.0,
+                _ => level as f64 * 3.0,
+            };
+
+            *scores
+                .entry(ancestor.id())
+                .or_insert_with(|| initial_score(element)) += score / divider;
+        }
+    }
+
+    // In document order, for the first of equal candidates to win.
+    let score_of = |node: NodeRef<Node>| {
+        scores
+            .get(&node.id())
+            .map(|score| score * (1.0 - lengths.link_density(node)))
+    };
+    let (top, top_score) =
+        root.descendants()
+            .filter_map(|node| score_of(node).map(|score| (node, score)))
+            .fold(None, |best: Option<(NodeRef<Node>, f64)>, candidate| {
+                match best {
+                    Some(best) if best.1 >= candidate.1 => Some(best),
+                    _ => Some(candidate),
+                }
+            })?;
+
+    let threshold = (top_score * 0.2).max(10.0);
+    let selected: Vec<_> = match top.parent() {
+        Some(parent) if parent.value().is_element() => parent
+            .children()
+            .filter(|sibling| {
+                *sibling == top ||
+                    is_related(
+                        *sibling,
+                        score_of(*sibling),
+                        threshold,
+                        &lengths,
+                    )
+            })
+            .collect(),
+        _ => vec![top],
+    };
+
+    let mut writer = Writer {
+        url,
+        lengths: &lengths,
+        html: String::new(),
+        text: String::new(),
+        line: String::new(),
+        space: false,
+        preformatted: 0,
+        headings: Vec::new(),
+    };
+    for node in selected {
+        writer.render(node);
+    }
+    writer.break_block();
+
+    (!writer.text.is_empty()).then(|| Article {
+        word_count: word_count(&writer.text),
+        text: writer.text,
+        headings: writer.headings,
+        html: writer.html,
+    })
+}
+
+/// Whether a sibling of the top candidate is part of the content: it scores
+/// close to the candidate, is a paragraph of text, or is a heading.
+fn is_related(
+    node: NodeRef<Node>,
+    score: Option<f64>,
+    threshold: f64,
+    lengths: &Lengths,
+) -> bool {
+    let Node::Element(element) = node.value() else {
+        return false;
+    };
+    if is_ignored(element) {
+        return false;
+    }
+    if score.is_some_and(|score| score >= threshold) {
+        return true;
+    }
+
+    match element.name() {
+        "p" => {
+            let text = text_of(node);
+            let length = text.chars().count();
+            let link_density = lengths.link_density(node);
+
+            (length > 80 && link_density < 0.25) ||
+                (length > 0 &&
+                    link_density == 0.0 &&
+                    text.ends_with(['.', '!', '?', '。']))
+        },
+        name => {
+            heading_level(name).is_some() && lengths.link_density(node) == 0.0
+        },
+    }
+}
+
+/// Gathers the blocks of text to score, in document order: paragraphs, and
+/// blocks without nested blocks.
+fn collect_blocks(node: NodeRef<Node>) -> Vec<NodeRef<Node>> {
+    let mut blocks = Vec::new();
+    // Walked with an explicit stack: pages can nest elements deeper than the
+    // call stack allows.
+    let mut stack: Vec<_> = node.children().rev().collect();
+
+    while let Some(child) = stack.pop() {
+        let Node::Element(element) = child.value() else {
+            continue;
+        };
+        if is_ignored(element) {
+            continue;
+        }
+
+        let is_block = match element.name() {
+            "p" | "pre" => true,
+            "td" | "blockquote" | "div" | "section" => {
+                !child.children().any(|child| {
+                    child.value().as_element().is_some_and(|element| {
+                        BLOCK_TAGS.contains(&element.name())
+                    })
+                })
+            },
+            _ => false,
+        };
+
+        if is_block {
+            blocks.push(child);
+        } else {
+            stack.extend(child.children().rev());
+        }
+    }
+
+    blocks
+}
+
+/// Whether an element is never part of the content.
+fn is_ignored(element: &Element) -> bool {
+    let name = element.name();
+    let hidden = element.attr("hidden").is_some() ||
+        element.attr("aria-hidden") == Some("true") ||
+        element.attr("style").is_some_and(|style| {
+            style.replace(' ', "").contains("display:none")
+        });
+
+    if IGNORED_TAGS.contains(&name) ||
+        hidden ||
+        element
+            .attr("role")
+            .is_some_and(|role| IGNORED_ROLES.contains(&role))
+    {
+        return true;
+    }
+    if matches!(name, "html" | "body" | "article" | "main" | "a") {
+        return false;
+    }
+
+    let names = format!(
+        "{} {}",
+        element.attr("class").unwrap_or_default(),
+        element.id().unwrap_or_default()
+    );
+    UNLIKELY.is_match(&names) && !MAYBE.is_match(&names)
+}
+
+/// Whether an element inside the content looks like boilerplate, such as a
+/// list of links.
+fn is_clutter(
+    node: NodeRef<Node>,
+    element: &Element,
+    lengths: &Lengths,
+) -> bool {
+    CLEANED_TAGS.contains(&element.name()) &&
+        (class_weight(element) < 0.0 || lengths.link_density(node) > 0.5)
+}
+
+/// Score of an element before the blocks it contains are counted.
+fn initial_score(element: &Element) -> f64 {
+    let tag_weight = match element.name() {
+        "article" | "main" => 10.0,
+        "div" => 5.0,
+        "pre" | "td" | "blockquote" => 3.0,
+        "address" | "ol" | "ul" | "dl" | "dd" | "dt" | "li" | "form" => -3.0,
+        "h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th" => -5.0,
+        _ => 0.0,
+    };
+
+    tag_weight + class_weight(element)
+}
+
+/// Weight of the class and id of an element.
+fn class_weight(element: &Element) -> f64 {
+    [element.attr("class"), element.id()]
+        .into_iter()
+        .flatten()
+        .map(|names| {
+            let mut weight = 0.0;
+            if NEGATIVE.is_match(names) {
+                weight -= 25.0;
+            }
+            if POSITIVE.is_match(names) {
+                weight += 25.0;
+            }
+            weight
+        })
+        .sum()
+}
+
+/// Length of the [text](text_of) of a node, in characters and words. Text
+/// nodes are separated by a space, so words never span two of them.
+#[derive(Debug, Default, Clone, Copy)]
+struct TextLength {
+    chars: usize,
+    words: usize,
+}
+
+impl TextLength {
+    fn of(text: &str) -> Self {
+        text.split_whitespace()
+            .fold(TextLength::default(), |length, word| TextLength {
+                chars: length.chars + word.chars().count(),
+                words: length.words + 1,
+            })
+    }
+
+    fn add(self, other: TextLength) -> Self {
+        TextLength {
+            chars: self.chars + other.chars,
+            words: self.words + other.words,
+        }
+    }
+
+    /// Number of characters of the whitespace-normalized text.
+    fn total(self) -> usize {
+        (self.chars + self.words).saturating_sub(1)
+    }
+}
+
+/// Lengths of the text and links of every node of a document, computed in
+/// one pass so that scoring stays linear in the size of the page.
+struct Lengths(HashMap<NodeId, (TextLength, usize)>);
+
+impl Lengths {
+    fn new(root: NodeRef<Node>) -> Self {
+        let mut lengths = HashMap::new();
+
+        // Children are closed before their parent.
+        for edge in root.traverse() {
+            let Edge::Close(node) = edge else {
+                continue;
+            };
+            if node.value().is_text() {
+                continue;
+            }
+
+            let mut text = TextLength::default();
+            let mut links = 0;
+            for child in node.children() {
+                match child.value() {
+                    Node::Text(content) => {
+                        text = text.add(TextLength::of(content));
+                    },
+                    Node::Element(element) => {
+                        let (child_text, child_links) = lengths
+                            .get(&child.id())
+                            .copied()
+                            .unwrap_or_default();
+                        if !is_ignored(element) {
+                            text = text.add(child_text);
+                        }
+                        links += child_links;
+                    },
+                    _ => {},
+                }
+            }
+            if node
+                .value()
+                .as_element()
+                .is_some_and(|element| element.name() == "a")
+            {
+                links += text.total();
+            }
+
+            lengths.insert(node.id(), (text, links));
+        }
+
+        Lengths(lengths)
+    }
+
+    /// Share of the text of a node inside links.
+    fn link_density(&self, node: NodeRef<Node>) -> f64 {
+        let Some((text, links)) = self.0.get(&node.id()) else {
+            return 0.0;
+        };
+        match text.total() {
+            0 => 0.0,
+            length => *links as f64 / length as f64,
+        }
+    }
+}
+
+/// Whitespace-normalized text of a node, without ignored elements.
+fn text_of(node: NodeRef<Node>) -> String {
+    let mut text = String::new();
+    let mut stack: Vec<_> = node.children().rev().collect();
+
+    while let Some(child) = stack.pop() {
+        match child.value() {
+            Node::Text(content) => {
+                text.push(' ');
+                text.push_str(content);
+            },
+            Node::Element(element) if !is_ignored(element) => {
+                stack.extend(child.children().rev());
+            },
+            _ => {},
+        }
+    }
+
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Level of a heading element.
+fn heading_level(name: &str) -> Option<u8> {
+    match name.as_bytes() {
+        [b'h', level @ b'1'..=b'6'] => Some(level - b'0'),
+        _ => None,
+    }
+}
+
+/// Number of words of a text. Chinese and Japanese are written without
+/// spaces, each of their characters is counted as a word.
+fn word_count(text: &str) -> usize {
+    let mut count = 0;
+    let mut in_word = false;
+
+    for c in text.chars() {
+        if is_ideograph(c) {
+            count += 1;
+            in_word = false;
+        } else if c.is_alphanumeric() {
+            count += usize::from(!in_word);
+            in_word = true;
+        } else if !(in_word && matches!(c, '\'' | '’' | '-')) {
+            in_word = false;
+        }
+    }
+
+    count
+}
+
+/// Whether a character is a Chinese character or a Japanese kana.
+fn is_ideograph(c: char) -> bool {
+    matches!(
+        c,
+        '\u{3040}'..='\u{30ff}' |
+            '\u{3400}'..='\u{4dbf}' |
+            '\u{4e00}'..='\u{9fff}' |
+            '\u{f900}'..='\u{faff}'
+    )
+}
+
+/// Escapes the text of an HTML element or attribute.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes the text and cleaned HTML of the content.
+struct Writer<'a> {
+    url: &'a Url,
+    lengths: &'a Lengths,
+    html: String,
+    text: String,
+    /// Text of the current block.
+    line: String,
+    /// Whether whitespace precedes the next text.
+    space: bool,
+    /// Depth of `<pre>` elements, whose whitespace is kept.
+    preformatted: usize,
+    headings: Vec<Heading>,
+}
+
+/// Element being written by the [Writer].
+struct Open {
+    /// Length of the HTML before the element.
+    start: usize,
+    /// Length of the HTML before the content of the element, if the element
+    /// is kept.
+    content: Option<usize>,
+}
+
+impl Writer<'_> {
+    /// Writes a node of the content and its descendants. Nested nodes are
+    /// walked without recursion, however deep they are.
+    fn render(&mut self, root: NodeRef<Node>) {
+        let mut open: Vec<Open> = Vec::new();
+        // Element left out with its descendants.
+        let mut skipped = None;
+
+        for edge in root.traverse() {
+            match edge {
+                Edge::Open(node) if skipped.is_none() => match node.value() {
+                    Node::Text(text) => self.push_text(text),
+                    Node::Element(element) => {
+                        match self.open_element(node, element, node == root) {
+                            Some(element) => open.push(element),
+                            None => skipped = Some(node.id()),
+                        }
+                    },
+                    _ => {},
+                },
+                Edge::Close(node) if skipped == Some(node.id()) => {
+                    skipped = None;
+                },
+                Edge::Close(node) if skipped.is_none() => {
+                    let Some(element) = node.value().as_element() else {
+                        continue;
+                    };
+                    if let Some(opened) = open.pop() {
+                        self.close_element(element.name(), opened);
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Starts writing an element. `None` if it is left out.
+    fn open_element(
+        &mut self,
+        node: NodeRef<Node>,
+        element: &Element,
+        root: bool,
+    ) -> Option<Open> {
+        if is_ignored(element) ||
+            (!root && is_clutter(node, element, self.lengths))
+        {
+            return None;
+        }
+
+        let name = element.name();
+        if BLOCK_TAGS.contains(&name) {
+            self.break_block();
+        }
+        if let Some(level) = heading_level(name) {
+            let text = text_of(node);
+            if text.is_empty() {
+                return None;
+            }
+            self.headings.push(Heading { level, text });
+        }
+
+        let start = self.html.len();
+        let kept = KEPT_TAGS.contains(&name) && self.open(element);
+
+        match name {
+            "br" => {
+                self.line.push('\n');
+                self.space = false;
+            },
+            "pre" => self.preformatted += 1,
+            _ => {},
+        }
+
+        Some(Open {
+            start,
+            content: kept.then_some(self.html.len()),
+        })
+    }
+
+    /// Ends writing an element started by
+    /// [open_element](Writer::open_element).
+    fn close_element(&mut self, name: &str, open: Open) {
+        if name == "pre" {
+            self.preformatted -= 1;
+        }
+
+        if let Some(content) = open.content {
+            if !VOID_TAGS.contains(&name) {
+                if self.html.len() == content {
+                    self.html.truncate(open.start);
+                } else {
+                    self.html.push_str(&format!("</{}>", name));
+                }
+            }
+        }
+        if BLOCK_TAGS.contains(&name) {
+            self.break_block();
+        }
+    }
+
+    /// Opens a kept element in the HTML. Images without source are dropped.
+    fn open(&mut self, element: &Element) -> bool {
+        let mut tag = format!("<{}", element.name());
+
+        let target = match element.name() {
+            "a" => Some(("href", element.attr("href"))),
+            "img" => Some((
+                "src",
+                element.attr("src").or_else(|| element.attr("data-src")),
+            )),
+            _ => None,
+        };
+        if let Some((attribute, target)) = target {
+            let target = target
+                .and_then(|target| self.url.join(target.trim()).ok())
+                .filter(|target| target.scheme() != "javascript");
+
+            match target {
+                Some(target) => tag.push_str(&format!(
+                    " {}=\"{}\"",
+                    attribute,
+                    escape(target.as_str())
+                )),
+                None if attribute == "src" => return false,
+                None => {},
+            }
+        }
+
+        for attribute in KEPT_ATTRIBUTES {
+            if let Some(value) = element.attr(attribute) {
+                tag.push_str(&format!(" {}=\"{}\"", attribute, escape(value)));
+            }
+        }
+
+        tag.push('>');
+        self.html.push_str(&tag);
+        true
+    }
+
+    fn push_text(&mut self, text: &str) {
+        if self.preformatted > 0 {
+            self.html.push_str(&escape(text));
+            self.line.push_str(text);
+            return;
+        }
+
+        let mut html = String::new();
+        for c in text.chars() {
+            let line_start = self.line.is_empty() || self.line.ends_with('\n');
+
+            if c.is_whitespace() {
+                if !self.space && !line_start {
+                    html.push(' ');
+                }
+                self.space = true;
+            } else {
+                if self.space && !line_start {
+                    self.line.push(' ');
+                }
+                self.space = false;
+                self.line.push(c);
+                html.push(c);
+            }
+        }
+
+        self.html.push_str(&escape(&html));
+    }
+
+    /// Ends the current block of text.
+    fn break_block(&mut self) {
+        let line = self.line.trim();
+        if !line.is_empty() {
+            if !self.text.is_empty() {
+                self.text.push_str("\n\n");
+            }
+            self.text.push_str(line);
+        }
+
+        self.line.clear();
+        self.space = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(body: &str) -> Article {
+        let url = Url::parse("https://gravitalia.com/blog/post").unwrap();
+        extract_article(body, &url).unwrap()
+    }
+
+    fn heading(level: u8, text: &str) -> Heading {
+        Heading {
+            level,
+            text: text.to_owned(),
+        }
+    }
+
+    #[test]
+    fn blog_article() {
+        let article =
+            extract(include_str!("../../fixtures/articles/blog.html"));
+
+        assert!(article
+            .text
+            .starts_with("Why we rewrote our crawler in Rust\n\nOur first"));
+        assert!(article.text.ends_with("as described in a previous post."));
+        for boilerplate in [
+            "About us",
+            "cookies",
+            "Share on",
+            "Great article",
+            "Popular posts",
+            "Copyright",
+            "dataLayer",
+        ] {
+            assert!(!article.text.contains(boilerplate), "{}", boilerplate);
+            assert!(!article.html.contains(boilerplate), "{}", boilerplate);
+        }
+
+        assert_eq!(
+            article.headings,
+            vec![
+                heading(1, "Why we rewrote our crawler in Rust"),
+                heading(2, "Memory first"),
+                heading(2, "What we lost"),
+            ]
+        );
+        assert!(article.html.contains(
+            "<img src=\"https://gravitalia.com/images/graph.png\" \
+             alt=\"Pages fetched per second\">"
+        ));
+        assert!(article.html.contains(
+            "<a href=\"https://gravitalia.com/blog/experiments\">as \
+             described in a previous post</a>"
+        ));
+        assert!(!article.html.contains("<div"));
+        assert_eq!(article.word_count, 130);
+    }
+
+    #[test]
+    fn news_article() {
+        let article =
+            extract(include_str!("../../fixtures/articles/news.html"));
+
+        assert_eq!(
+            article.headings,
+            vec![heading(1, "Le Conseil municipal vote le budget 2024")]
+        );
+        assert!(article.text.contains(
+            "rénovation énergétique des bâtiments publics.\n\nL'opposition"
+        ));
+        assert!(article.text.ends_with("validé par la préfecture."));
+        for boilerplate in [
+            "Accueil",
+            "Publicité",
+            "À lire aussi",
+            "Les plus lus",
+            "Mentions",
+        ] {
+            assert!(!article.text.contains(boilerplate), "{}", boilerplate);
+        }
+    }
+
+    #[test]
+    fn documentation_article() {
+        let article =
+            extract(include_str!("../../fixtures/articles/docs.html"));
+
+        assert_eq!(
+            article.headings,
+            vec![
+                heading(1, "Configuration"),
+                heading(2, "Allowed domains"),
+                heading(3, "Depth"),
+            ]
+        );
+        assert!(article.text.contains(
+            "Crawler::new()\n    .allowed_domains(vec![\"example.com\".to_owned()])"
+        ));
+        assert!(article
+            .html
+            .contains("<tr><th>Option</th><th>Default</th></tr>"));
+        assert!(!article.text.contains("Frequently asked"));
+        assert!(!article.text.contains("static site generator"));
+        assert!(article.text.ends_with("日本語のドキュメントもあります。"));
+    }
+
+    #[test]
+    fn no_article() {
+        let url = Url::parse("https://gravitalia.com/").unwrap();
+
+        assert_eq!(extract_article("<p>Short.</p>", &url), None);
+        assert_eq!(
+            extract_article(
+                "<nav><p>A navigation menu with plenty of words in it.</p></nav>",
+                &url
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn deeply_nested_article() {
+        // Deeper than a 2 MiB stack allows when walked recursively.
+        let depth = 10_000;
+        let body = format!(
+            "{}<p>{}</p>{}",
+            "<div>".repeat(depth),
+            "The content is nested very deep, but it is still found. "
+                .repeat(3),
+            "</div>".repeat(depth)
+        );
+
+        let article = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || extract(&body))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert!(article.text.starts_with("The content is nested very deep"));
+        assert_eq!(article.word_count, 33);
+    }
+
+    #[test]
+    fn nested_blocks() {
+        // Each `<div>` holds a block and is scored: walking the subtree of
+        // every candidate took about 45 seconds on this page.
+        let depth = 1_000;
+        let body = format!(
+            "{}{}",
+            "<div><p>Some text, with a comma, long enough to be scored. \
+             <a href=\"/\">link</a></p>"
+                .repeat(depth),
+            "</div>".repeat(depth)
+        );
+        let url = Url::parse("https://example.com/").unwrap();
+        let document = Html::parse_document(&body);
+
+        let start = std::time::Instant::now();
+        let article = article(&document, &url).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert_eq!(article.word_count, depth * 11);
+    }
+
+    #[test]
+    fn words() {
+        assert_eq!(word_count("Hello, world!"), 2);
+        assert_eq!(word_count("L'opposition a dénoncé - 3 % de hausse"), 6);
+        assert_eq!(word_count("state-of-the-art"), 1);
+        assert_eq!(word_count("日本語です"), 5);
+    }
+}
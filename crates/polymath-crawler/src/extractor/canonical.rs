@@ -0,0 +1,277 @@
+//! Canonical URL and
+//! [hreflang](https://developers.google.com/search/docs/specialty/international/localized-versions)
+//! alternates of a page, declared by its `<link>` tags or its
+//! [`Link`](https://www.rfc-editor.org/rfc/rfc8288) header.
+
+use url::Url;
+
+use crate::page::FetchedPage;
+
+/// Translation or regional variant of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alternate {
+    /// `hreflang` of the variant: a language tag such as `fr` or `en-GB`, or
+    /// `x-default`.
+    pub language: String,
+    /// URL of the variant.
+    pub url: Url,
+}
+
+/// Translations of a page, grouped in a cluster.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanguageCluster {
+    /// Identifier of the cluster: the smallest URL of the page and its
+    /// alternates. Translations declaring each other share it.
+    pub id: Url,
+    /// Variants of the page, sorted by language, without duplicates.
+    pub alternates: Vec<Alternate>,
+}
+
+impl LanguageCluster {
+    /// Cluster of the page at `url`, usually its canonical URL. `None`
+    /// without alternates.
+    pub fn new(url: &Url, mut alternates: Vec<Alternate>) -> Option<Self> {
+        for alternate in &mut alternates {
+            alternate.language = alternate.language.to_lowercase();
+        }
+        alternates.sort_by(|a, b| {
+            (&a.language, a.url.as_str()).cmp(&(&b.language, b.url.as_str()))
+        });
+        alternates.dedup();
+
+        let id = alternates
+            .iter()
+            .map(|alternate| &alternate.url)
+            .chain([url])
+            .min_by_key(|url| url.as_str())?
+            .clone();
+
+        (!alternates.is_empty()).then_some(LanguageCluster { id, alternates })
+    }
+
+    /// URL of the variant for a language, ignoring case.
+    pub fn get(&self, language: &str) -> Option<&Url> {
+        self.alternates
+            .iter()
+            .find(|alternate| alternate.language.eq_ignore_ascii_case(language))
+            .map(|alternate| &alternate.url)
+    }
+}
+
+/// Canonical URL and alternates of an HTTP `Link` header.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LinkHeader {
+    /// Target of the first `rel="canonical"` link.
+    pub canonical: Option<Url>,
+    /// Targets of the `rel="alternate"` links with a `hreflang` parameter.
+    pub alternates: Vec<Alternate>,
+}
+
+impl LinkHeader {
+    /// Parses the value of a `Link` header, such as
+    /// `<https://gravitalia.com/>; rel="canonical"`. Several headers can be
+    /// joined with commas. Relative targets are resolved against `url`.
+    pub fn parse(value: &str, url: &Url) -> Self {
+        let mut header = LinkHeader::default();
+
+        for (target, params) in links(value) {
+            let Ok(target) = url.join(target.trim()) else {
+                continue;
+            };
+            let param = |name: &str| {
+                params
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.as_str())
+            };
+            let rel = param("rel").unwrap_or_default();
+            let has_rel = |name: &str| {
+                rel.split_whitespace()
+                    .any(|rel| rel.eq_ignore_ascii_case(name))
+            };
+
+            if has_rel("canonical") && header.canonical.is_none() {
+                header.canonical = Some(target);
+            } else if let Some(language) = param("hreflang") {
+                if has_rel("alternate") {
+                    header.alternates.push(Alternate {
+                        language: language.to_owned(),
+                        url: target,
+                    });
+                }
+            }
+        }
+
+        header
+    }
+}
+
+/// Targets of a `Link` header value, with their parameters. Parameter names
+/// are lowercased and quotes around values removed.
+fn links(value: &str) -> Vec<(&str, Vec<(String, String)>)> {
+    let mut links = Vec::new();
+    let mut rest = value;
+
+    loop {
+        rest = rest.trim_start_matches(|c: char| c == ',' || c.is_whitespace());
+        let Some((target, mut tail)) =
+            rest.strip_prefix('<').and_then(|rest| rest.split_once('>'))
+        else {
+            break;
+        };
+
+        let mut params = Vec::new();
+        while let Some(param) = tail.trim_start().strip_prefix(';') {
+            let param = param.trim_start();
+            let end = param.find(['=', ';', ',']).unwrap_or(param.len());
+            let (name, after) = param.split_at(end);
+
+            let (value, after) = match after.strip_prefix('=') {
+                Some(value) => {
+                    let value = value.trim_start();
+                    match value.strip_prefix('"') {
+                        Some(quoted) => {
+                            let end = quoted.find('"').unwrap_or(quoted.len());
+                            (
+                                &quoted[..end],
+                                quoted.get(end + 1..).unwrap_or(""),
+                            )
+                        },
+                        None => {
+                            let end =
+                                value.find([';', ',']).unwrap_or(value.len());
+                            (value[..end].trim_end(), &value[end..])
+                        },
+                    }
+                },
+                None => ("", after),
+            };
+
+            params.push((name.trim().to_lowercase(), value.to_owned()));
+            tail = after;
+        }
+
+        links.push((target, params));
+        rest = tail;
+    }
+
+    links
+}
+
+/// Reads the canonical URL and language cluster of a page, from its
+/// [metadata](FetchedPage::metadata) first and its `Link` headers then.
+pub(crate) fn resolve(page: &mut FetchedPage) {
+    let value = page
+        .headers
+        .iter()
+        .filter(|(name, _)| name == "link")
+        .map(|(_, value)| value.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let header = LinkHeader::parse(&value, &page.final_url);
+
+    let (canonical, mut alternates) = match &page.metadata {
+        Some(metadata) => {
+            (metadata.canonical.clone(), metadata.alternates.clone())
+        },
+        None => (None, Vec::new()),
+    };
+    alternates.extend(header.alternates);
+
+    page.canonical = canonical.or(header.canonical);
+    page.language_cluster = LanguageCluster::new(
+        page.canonical.as_ref().unwrap_or(&page.final_url),
+        alternates,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn alternate(language: &str, target: &str) -> Alternate {
+        Alternate {
+            language: language.to_owned(),
+            url: url(target),
+        }
+    }
+
+    #[test]
+    fn link_header() {
+        let header = LinkHeader::parse(
+            r#"</style.css>; rel=preload; as=style,
+            <https://gravitalia.com/fr/>; rel="alternate"; hreflang="fr",
+            </en/>; REL="Alternate"; hreflang=en-GB,
+            <https://gravitalia.com/>; rel="canonical",
+            </other>; rel="canonical",
+            </de/>; hreflang="de""#,
+            &url("https://gravitalia.com/en/?v=2"),
+        );
+
+        assert_eq!(header.canonical, Some(url("https://gravitalia.com/")));
+        assert_eq!(
+            header.alternates,
+            vec![
+                alternate("fr", "https://gravitalia.com/fr/"),
+                alternate("en-GB", "https://gravitalia.com/en/"),
+            ]
+        );
+
+        assert_eq!(
+            LinkHeader::parse("not a link", &url("https://gravitalia.com/")),
+            LinkHeader::default()
+        );
+        assert_eq!(
+            LinkHeader::parse(
+                r#"<https://gravitalia.com/a;b,c>; rel="canonical"; title="a, b; c""#,
+                &url("https://gravitalia.com/")
+            )
+            .canonical,
+            Some(url("https://gravitalia.com/a;b,c"))
+        );
+    }
+
+    #[test]
+    fn language_cluster() {
+        let alternates = vec![
+            alternate("FR", "https://gravitalia.com/fr/"),
+            alternate("x-default", "https://gravitalia.com/"),
+            alternate("fr", "https://gravitalia.com/fr/"),
+            alternate("en", "https://gravitalia.com/en/"),
+        ];
+
+        let cluster = LanguageCluster::new(
+            &url("https://gravitalia.com/fr/"),
+            alternates.clone(),
+        )
+        .unwrap();
+        assert_eq!(cluster.id, url("https://gravitalia.com/"));
+        assert_eq!(
+            cluster.alternates,
+            vec![
+                alternate("en", "https://gravitalia.com/en/"),
+                alternate("fr", "https://gravitalia.com/fr/"),
+                alternate("x-default", "https://gravitalia.com/"),
+            ]
+        );
+        assert_eq!(cluster.get("EN"), Some(&url("https://gravitalia.com/en/")));
+        assert_eq!(cluster.get("de"), None);
+
+        // Every translation gets the same identifier.
+        let other = LanguageCluster::new(
+            &url("https://gravitalia.com/en/"),
+            alternates,
+        )
+        .unwrap();
+        assert_eq!(other.id, cluster.id);
+
+        assert_eq!(
+            LanguageCluster::new(&url("https://gravitalia.com/"), Vec::new()),
+            None
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Dates found in document metadata.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Number of seconds in a day.
+const DAY: i64 = 86_400;
+
+/// Time of a date of the proleptic Gregorian calendar, `seconds` after
+/// midnight, in a time zone `offset` seconds ahead of UTC. `None` for years
+/// outside 0 to 9999, times beyond a day or offsets beyond a day.
+pub(crate) fn timestamp(
+    year: i64,
+    month: i64,
+    day: i64,
+    seconds: i64,
+    offset: i64,
+) -> Option<SystemTime> {
+    if !(0..=9999).contains(&year) ||
+        !(1..=12).contains(&month) ||
+        !(1..=31).contains(&day) ||
+        !(0..=DAY).contains(&seconds) ||
+        !(-DAY..=DAY).contains(&offset)
+    {
+        return None;
+    }
+
+    let timestamp = days_from_civil(year, month, day) * DAY + seconds - offset;
+
+    Some(UNIX_EPOCH + Duration::from_secs(u64::try_from(timestamp).ok()?))
+}
+
+/// Parses a [W3C date](https://www.w3.org/TR/NOTE-datetime), such as
+/// `2024-01-31`, `2024-01-31T12:00:00Z` or `2024-01-31T12:00:00.5+01:00`.
+/// Times without offset are read as UTC.
+pub(crate) fn parse_w3c(date: &str) -> Option<SystemTime> {
+    let (date, time) = date.trim().split_once('T').unwrap_or((date, ""));
+
+    let mut fields = date.split('-');
+    let year = fields.next()?.parse().ok()?;
+    let month = fields.next().map_or(Ok(1), str::parse).ok()?;
+    let day = fields.next().map_or(Ok(1), str::parse).ok()?;
+
+    let (clock, offset) = time
+        .find(['Z', '+', '-'])
+        .map_or((time, ""), |index| time.split_at(index));
+    let seconds = clock
+        .split(':')
+        .filter(|field| !field.is_empty())
+        .zip([3600, 60, 1])
+        .map(|(field, unit)| {
+            let field = field.split('.').next().unwrap_or_default();
+            field.parse::<i64>().ok()?.checked_mul(unit)
+        })
+        .try_fold(0, |seconds: i64, field| seconds.checked_add(field?))?;
+
+    let offset = match offset.split_at_checked(1) {
+        Some((sign @ ("+" | "-"), offset)) => {
+            let (hours, minutes) =
+                offset.split_once(':').unwrap_or((offset, "0"));
+            let offset =
+                hours.parse::<i64>().ok()?.checked_mul(3600)?.checked_add(
+                    minutes.parse::<i64>().ok()?.checked_mul(60)?,
+                )?;
+
+            if sign == "-" {
+                -offset
+            } else {
+                offset
+            }
+        },
+        _ => 0,
+    };
+
+    timestamp(year, month, day, seconds, offset)
+}
+
+/// Number of days between 1970-01-01 and a date of the proleptic Gregorian
+/// calendar.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146_097 + day_of_era - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(secs: u64) -> Option<SystemTime> {
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    #[test]
+    fn w3c_dates() {
+        assert_eq!(parse_w3c("1970-01-01T00:00:00Z"), at(0));
+        assert_eq!(parse_w3c("2024-01-31"), at(1_706_659_200));
+        assert_eq!(parse_w3c("2024-01-31T12:00:00+01:00"), at(1_706_698_800));
+        assert_eq!(
+            parse_w3c("2024-01-31T12:00:00.25-05:30"),
+            at(1_706_722_200)
+        );
+        assert_eq!(parse_w3c("2000-02-29T00:00"), at(951_782_400));
+        assert_eq!(parse_w3c("2024-13-01"), None);
+        assert_eq!(parse_w3c("demain"), None);
+
+        // Out of range fields do not overflow.
+        assert_eq!(parse_w3c("9999999999999999-01-01"), None);
+        assert_eq!(parse_w3c("2024-01-01T99999999999999999:00"), None);
+        assert_eq!(parse_w3c("2024-01-01T9999999999999999:00"), None);
+        assert_eq!(parse_w3c("2024-01-01T00:00+9999999999999999:00"), None);
+        assert_eq!(parse_w3c("-9999999999999999-01-01"), None);
+    }
+}
@@ -0,0 +1,352 @@
+//! Image metadata extraction.
+//!
+//! Images are decoded to read their dimensions and compute a
+//! [perceptual hash](https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html),
+//! and their EXIF tags are read when present.
+
+use std::io::Cursor;
+use std::time::SystemTime;
+
+use exif::{In, Tag, Value};
+use image::imageops::FilterType;
+use image::{DynamicImage, ImageReader};
+use polymath_error::{Error, ErrorType::Scraper, ScraperError};
+use url::Url;
+
+use super::date;
+use super::link::Link;
+
+/// Media types of the supported image formats.
+const MEDIA_TYPES: [&str; 4] =
+    ["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+/// Extensions of the supported image formats.
+const EXTENSIONS: [&str; 5] = ["jpeg", "jpg", "png", "gif", "webp"];
+
+/// Format of an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ImageFormat {
+    /// JPEG image.
+    Jpeg,
+    /// PNG image.
+    Png,
+    /// GIF image, possibly animated.
+    Gif,
+    /// WebP image.
+    WebP,
+}
+
+/// GPS position of an image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GpsPosition {
+    /// Latitude, in degrees. Negative in the southern hemisphere.
+    pub latitude: f64,
+    /// Longitude, in degrees. Negative west of Greenwich.
+    pub longitude: f64,
+    /// Altitude, in meters. Negative below sea level.
+    pub altitude: Option<f64>,
+}
+
+/// EXIF tags of an image.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Exif {
+    /// Manufacturer of the camera.
+    pub make: Option<String>,
+    /// Model of the camera.
+    pub model: Option<String>,
+    /// When the picture was taken. Read as UTC without time zone offset.
+    pub taken_at: Option<SystemTime>,
+    /// Where the picture was taken.
+    pub position: Option<GpsPosition>,
+    /// [Orientation](https://www.exif.org/Exif2-2.PDF#page=24) of the
+    /// picture, from 1 (upright) to 8.
+    pub orientation: Option<u16>,
+}
+
+/// Context of the page on which an image was found.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ImageContext {
+    /// `alt` attribute of the image, or text of the link to it.
+    pub alt: Option<String>,
+    /// `title` attribute of the image.
+    pub title: Option<String>,
+    /// `<figcaption>` of the `<figure>` containing the image.
+    pub caption: Option<String>,
+    /// Page on which the image was found. `None` for seeds.
+    pub referrer: Option<Url>,
+}
+
+impl ImageContext {
+    /// Context of an image found through a link of a page.
+    pub fn new(link: Option<&Link>, referrer: Option<&Url>) -> Self {
+        ImageContext {
+            alt: link.and_then(|link| link.text.clone()),
+            title: link.and_then(|link| link.title.clone()),
+            caption: link.and_then(|link| link.caption.clone()),
+            referrer: referrer.cloned(),
+        }
+    }
+}
+
+/// Dimensions, metadata and perceptual hash of an image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    /// Format of the image, read from its content.
+    pub format: ImageFormat,
+    /// Width, in pixels, before [orientation](Exif::orientation) is applied.
+    pub width: u32,
+    /// Height, in pixels, before [orientation](Exif::orientation) is applied.
+    pub height: u32,
+    /// EXIF tags. Empty if the image has none.
+    pub exif: Exif,
+    /// [Difference hash](https://www.hackerfactor.com/blog/index.php?/archives/529-Kind-of-Like-That.html)
+    /// of the image. Similar images have close hashes, see
+    /// [distance](Image::distance).
+    pub hash: u64,
+    /// Context of the page on which the image was found.
+    pub context: ImageContext,
+}
+
+impl Image {
+    /// Number of different bits between the hashes of two images. Below 10,
+    /// the images are most likely the same picture, resized or recompressed.
+    pub fn distance(&self, other: &Image) -> u32 {
+        (self.hash ^ other.hash).count_ones()
+    }
+}
+
+/// Whether a response is a supported image, from its media type or, for
+/// generic binary responses, the extension of its URL.
+pub(crate) fn is_image(
+    media_type: Option<&str>,
+    extension: Option<&str>,
+) -> bool {
+    match media_type {
+        Some(media_type) if media_type != "application/octet-stream" => {
+            MEDIA_TYPES.contains(&media_type)
+        },
+        _ => extension.is_some_and(|extension| EXTENSIONS.contains(&extension)),
+    }
+}
+
+/// Extracts the dimensions, EXIF tags and perceptual hash of an image. The
+/// format is recognized from the content of the image.
+///
+/// The [context](Image::context) is left empty.
+pub fn extract_image(body: &[u8], url: &Url) -> Result<Image, Error> {
+    let invalid = |cause: Option<Box<dyn std::error::Error + Send + Sync>>| {
+        Error::new(
+            Scraper(ScraperError::InvalidDocument),
+            cause,
+            Some(format!("while reading image {}", url)),
+        )
+    };
+
+    let reader = ImageReader::new(Cursor::new(body))
+        .with_guessed_format()
+        .map_err(|e| invalid(Some(Box::new(e))))?;
+    let format = match reader.format() {
+        Some(image::ImageFormat::Jpeg) => ImageFormat::Jpeg,
+        Some(image::ImageFormat::Png) => ImageFormat::Png,
+        Some(image::ImageFormat::Gif) => ImageFormat::Gif,
+        Some(image::ImageFormat::WebP) => ImageFormat::WebP,
+        _ => return Err(invalid(None)),
+    };
+    let image = reader.decode().map_err(|e| invalid(Some(Box::new(e))))?;
+
+    Ok(Image {
+        format,
+        width: image.width(),
+        height: image.height(),
+        exif: read_exif(body),
+        hash: dhash(&image),
+        context: ImageContext::default(),
+    })
+}
+
+/// Difference hash of an image: each bit tells whether a pixel of the image,
+/// shrunk to 9×8 grayscale pixels, is darker than its right neighbour.
+fn dhash(image: &DynamicImage) -> u64 {
+    let small = image.resize_exact(9, 8, FilterType::Triangle).to_luma8();
+
+    (0..8)
+        .flat_map(|y| (0..8).map(move |x| (x, y)))
+        .fold(0, |hash, (x, y)| {
+            let darker =
+                small.get_pixel(x, y)[0] < small.get_pixel(x + 1, y)[0];
+            hash << 1 | u64::from(darker)
+        })
+}
+
+/// EXIF tags of an image. Empty if they are missing or cannot be read.
+fn read_exif(body: &[u8]) -> Exif {
+    let Ok(exif) =
+        exif::Reader::new().read_from_container(&mut Cursor::new(body))
+    else {
+        return Exif::default();
+    };
+    let value =
+        |tag| exif.get_field(tag, In::PRIMARY).map(|field| &field.value);
+    let text = |tag| match value(tag) {
+        Some(Value::Ascii(values)) => values
+            .first()
+            .map(|text| String::from_utf8_lossy(text).trim().to_owned())
+            .filter(|text| !text.is_empty()),
+        _ => None,
+    };
+    let rationals = |tag| match value(tag) {
+        Some(Value::Rational(values)) => {
+            values.iter().map(|value| value.to_f64()).collect()
+        },
+        _ => Vec::new(),
+    };
+
+    // Degrees, minutes and seconds, negative in the `negative` direction.
+    let coordinate = |tag, reference, negative: &str| {
+        let degrees = rationals(tag)
+            .iter()
+            .zip([1.0, 60.0, 3600.0])
+            .map(|(value, unit)| value / unit)
+            .sum::<f64>();
+
+        (value(tag).is_some() && degrees.is_finite()).then(|| {
+            if text(reference).as_deref() == Some(negative) {
+                -degrees
+            } else {
+                degrees
+            }
+        })
+    };
+    let position = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S")
+        .zip(coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W"))
+        .map(|(latitude, longitude)| GpsPosition {
+            latitude,
+            longitude,
+            altitude: rationals(Tag::GPSAltitude).first().map(|altitude| {
+                match value(Tag::GPSAltitudeRef) {
+                    Some(reference) if reference.get_uint(0) == Some(1) => {
+                        -altitude
+                    },
+                    _ => *altitude,
+                }
+            }),
+        });
+
+    let taken_at = text(Tag::DateTimeOriginal)
+        .or_else(|| text(Tag::DateTime))
+        .and_then(|date| {
+            parse_date(&date, text(Tag::OffsetTimeOriginal).as_deref())
+        });
+
+    Exif {
+        make: text(Tag::Make),
+        model: text(Tag::Model),
+        taken_at,
+        position,
+        orientation: value(Tag::Orientation)
+            .and_then(|orientation| orientation.get_uint(0))
+            .and_then(|orientation| u16::try_from(orientation).ok()),
+    }
+}
+
+/// Parses an EXIF date, such as `2024:01:31 12:00:00`, with its time zone
+/// offset, such as `+01:00`.
+fn parse_date(date: &str, offset: Option<&str>) -> Option<SystemTime> {
+    let (day, time) = date.trim().split_once(' ')?;
+
+    date::parse_w3c(&format!(
+        "{}T{}{}",
+        day.replace(':', "-"),
+        time,
+        offset.unwrap_or_default()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use image::{ImageBuffer, Luma};
+
+    use super::*;
+
+    fn url() -> Url {
+        Url::parse("https://cdn.gravitalia.com/photo.jpg").unwrap()
+    }
+
+    /// Encodes a grayscale gradient, darker to the right if `reversed`.
+    fn gradient(width: u32, height: u32, reversed: bool) -> Vec<u8> {
+        let image = ImageBuffer::from_fn(width, height, |x, _| {
+            let value = (x * 255 / (width - 1)) as u8;
+            Luma([if reversed { 255 - value } else { value }])
+        });
+        let mut body = Vec::new();
+        DynamicImage::ImageLuma8(image)
+            .write_to(&mut Cursor::new(&mut body), image::ImageFormat::Png)
+            .unwrap();
+
+        body
+    }
+
+    #[test]
+    fn image_extraction() {
+        let image =
+            extract_image(include_bytes!("../../fixtures/photo.jpg"), &url())
+                .unwrap();
+
+        assert_eq!(image.format, ImageFormat::Jpeg);
+        assert_eq!((image.width, image.height), (64, 48));
+        assert_eq!(image.exif.make.as_deref(), Some("Canon"));
+        assert_eq!(image.exif.model.as_deref(), Some("Canon EOS R6"));
+        assert_eq!(image.exif.orientation, Some(6));
+        assert_eq!(
+            image.exif.taken_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_706_698_800))
+        );
+
+        let position = image.exif.position.unwrap();
+        assert!((position.latitude - 48.856_667).abs() < 1e-6);
+        assert!((position.longitude - 2.35).abs() < 1e-6);
+        assert_eq!(position.altitude, Some(35.0));
+        assert_eq!(image.context, ImageContext::default());
+    }
+
+    #[test]
+    fn image_hash() {
+        let image = extract_image(&gradient(90, 80, false), &url()).unwrap();
+        let resized =
+            extract_image(&gradient(300, 200, false), &url()).unwrap();
+        let reversed = extract_image(&gradient(90, 80, true), &url()).unwrap();
+
+        assert_eq!(image.format, ImageFormat::Png);
+        assert_eq!((image.width, image.height), (90, 80));
+        assert_eq!(image.exif, Exif::default());
+        assert_eq!(image.hash, u64::MAX);
+        assert!(image.distance(&resized) < 10);
+        assert_eq!(image.distance(&reversed), 64);
+    }
+
+    #[test]
+    fn invalid_image() {
+        for body in [&b"GIF89a broken"[..], b"<svg></svg>"] {
+            assert!(matches!(
+                extract_image(body, &url()).unwrap_err().error_type,
+                Scraper(ScraperError::InvalidDocument)
+            ));
+        }
+    }
+
+    #[test]
+    fn exif_dates() {
+        let at = |secs| Some(UNIX_EPOCH + Duration::from_secs(secs));
+
+        assert_eq!(parse_date("1970:01:01 00:00:00", None), at(0));
+        assert_eq!(
+            parse_date("2024:01:31 12:00:00", Some("-05:30")),
+            at(1_706_722_200)
+        );
+        assert_eq!(parse_date("0000:00:00 00:00:00", None), None);
+        assert_eq!(parse_date("    :  :     :  :  ", None), None);
+    }
+}
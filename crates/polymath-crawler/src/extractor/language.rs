@@ -0,0 +1,274 @@
+//! Language identification.
+//!
+//! The languages declared by a page, through its `lang` attribute,
+//! `Content-Language` header or `og:locale`, are weighed against the one
+//! [detected](https://github.com/greyblake/whatlang-rs) from the trigrams of
+//! its text.
+
+use whatlang::Lang;
+
+use crate::page::FetchedPage;
+
+/// Confidence given to a declared language. A detection less confident
+/// than it does not override the declaration.
+const DECLARED_CONFIDENCE: f64 = 0.6;
+
+/// Number of characters of text read to detect its language.
+const MAX_TEXT: usize = 4096;
+
+/// ISO 639-1 codes of the languages which can be detected.
+const ISO_639_1: [(Lang, &str); 70] = [
+    (Lang::Afr, "af"),
+    (Lang::Aka, "ak"),
+    (Lang::Amh, "am"),
+    (Lang::Ara, "ar"),
+    (Lang::Aze, "az"),
+    (Lang::Bel, "be"),
+    (Lang::Ben, "bn"),
+    (Lang::Bul, "bg"),
+    (Lang::Cat, "ca"),
+    (Lang::Ces, "cs"),
+    (Lang::Cmn, "zh"),
+    (Lang::Cym, "cy"),
+    (Lang::Dan, "da"),
+    (Lang::Deu, "de"),
+    (Lang::Ell, "el"),
+    (Lang::Eng, "en"),
+    (Lang::Epo, "eo"),
+    (Lang::Est, "et"),
+    (Lang::Fin, "fi"),
+    (Lang::Fra, "fr"),
+    (Lang::Guj, "gu"),
+    (Lang::Heb, "he"),
+    (Lang::Hin, "hi"),
+    (Lang::Hrv, "hr"),
+    (Lang::Hun, "hu"),
+    (Lang::Hye, "hy"),
+    (Lang::Ind, "id"),
+    (Lang::Ita, "it"),
+    (Lang::Jav, "jv"),
+    (Lang::Jpn, "ja"),
+    (Lang::Kan, "kn"),
+    (Lang::Kat, "ka"),
+    (Lang::Khm, "km"),
+    (Lang::Kor, "ko"),
+    (Lang::Lat, "la"),
+    (Lang::Lav, "lv"),
+    (Lang::Lit, "lt"),
+    (Lang::Mal, "ml"),
+    (Lang::Mar, "mr"),
+    (Lang::Mkd, "mk"),
+    (Lang::Mya, "my"),
+    (Lang::Nep, "ne"),
+    (Lang::Nld, "nl"),
+    (Lang::Nob, "nb"),
+    (Lang::Ori, "or"),
+    (Lang::Pan, "pa"),
+    (Lang::Pes, "fa"),
+    (Lang::Pol, "pl"),
+    (Lang::Por, "pt"),
+    (Lang::Ron, "ro"),
+    (Lang::Rus, "ru"),
+    (Lang::Sin, "si"),
+    (Lang::Slk, "sk"),
+    (Lang::Slv, "sl"),
+    (Lang::Sna, "sn"),
+    (Lang::Spa, "es"),
+    (Lang::Srp, "sr"),
+    (Lang::Swe, "sv"),
+    (Lang::Tam, "ta"),
+    (Lang::Tel, "te"),
+    (Lang::Tgl, "tl"),
+    (Lang::Tha, "th"),
+    (Lang::Tuk, "tk"),
+    (Lang::Tur, "tr"),
+    (Lang::Ukr, "uk"),
+    (Lang::Urd, "ur"),
+    (Lang::Uzb, "uz"),
+    (Lang::Vie, "vi"),
+    (Lang::Yid, "yi"),
+    (Lang::Zul, "zu"),
+];
+
+/// Where the language of a page comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LanguageSource {
+    /// Declared by the page, its text being too short or ambiguous.
+    Declared,
+    /// Detected from the text, the page declaring none or another one.
+    Detected,
+    /// Declared by the page and confirmed by its text.
+    Both,
+}
+
+/// Language of a page.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Language {
+    /// [ISO 639-1](https://www.loc.gov/standards/iso639-2/php/code_list.php)
+    /// code of the language, such as `fr`.
+    pub code: String,
+    /// Confidence in the language, from 0 to 1.
+    pub confidence: f64,
+    /// Where the language comes from.
+    pub source: LanguageSource,
+}
+
+/// Identifies the language of a text, given the languages declared for it
+/// as [BCP 47](https://www.rfc-editor.org/rfc/rfc5646) tags (such as
+/// `en-GB`) or locales (such as `fr_FR`), in order of preference.
+///
+/// The most confident of the detected and the first declared languages is
+/// kept. When they agree, they reinforce each other. `None` without text nor
+/// valid declaration.
+pub fn detect_language(text: Option<&str>, hints: &[&str]) -> Option<Language> {
+    let hints: Vec<String> = hints
+        .iter()
+        .filter_map(|hint| primary_language(hint))
+        .collect();
+    let detected = text.and_then(|text| {
+        let text = match text.char_indices().nth(MAX_TEXT) {
+            Some((end, _)) => &text[..end],
+            None => text,
+        };
+        let info = whatlang::detect(text)?;
+
+        Some((iso_639_1(info.lang())?, info.confidence()))
+    });
+
+    match (detected, hints.first()) {
+        (Some((code, confidence)), _)
+            if hints.iter().any(|hint| hint == code) =>
+        {
+            Some(Language {
+                code: code.to_owned(),
+                confidence: 1.0 -
+                    (1.0 - confidence) * (1.0 - DECLARED_CONFIDENCE),
+                source: LanguageSource::Both,
+            })
+        },
+        (_, Some(hint))
+            if detected.is_none_or(|(_, confidence)| {
+                confidence <= DECLARED_CONFIDENCE
+            }) =>
+        {
+            Some(Language {
+                code: hint.clone(),
+                confidence: DECLARED_CONFIDENCE,
+                source: LanguageSource::Declared,
+            })
+        },
+        (Some((code, confidence)), _) => Some(Language {
+            code: code.to_owned(),
+            confidence,
+            source: LanguageSource::Detected,
+        }),
+        (None, _) => None,
+    }
+}
+
+/// ISO 639-1 code of a detected language.
+fn iso_639_1(lang: Lang) -> Option<&'static str> {
+    ISO_639_1
+        .iter()
+        .find(|(known, _)| *known == lang)
+        .map(|(_, code)| *code)
+}
+
+/// ISO 639-1 code of the primary language of a tag or locale. ISO 639-3
+/// codes of detectable languages and deprecated codes are translated; tags
+/// without a language, such as `und` or `x-default`, are ignored.
+fn primary_language(tag: &str) -> Option<String> {
+    let primary = tag.trim().split(['-', '_']).next()?.to_lowercase();
+
+    let code = match primary.as_str() {
+        "iw" => "he",
+        "in" => "id",
+        "ji" => "yi",
+        "no" => "nb",
+        code if code.len() == 3 => {
+            return Lang::from_code(code)
+                .and_then(iso_639_1)
+                .map(str::to_owned);
+        },
+        code => code,
+    };
+
+    // Two-letter codes are kept even if their language cannot be detected.
+    (code.len() == 2 && code.bytes().all(|b| b.is_ascii_lowercase()))
+        .then(|| code.to_owned())
+}
+
+/// Identifies the language of a page from its [article](FetchedPage::article)
+/// or [document](FetchedPage::document) text, and the languages declared by
+/// its `<html lang>`, `Content-Language` and `og:locale`.
+pub(crate) fn resolve(page: &mut FetchedPage) {
+    let mut hints = Vec::new();
+    if let Some(metadata) = &page.metadata {
+        hints.extend(metadata.language.as_deref());
+    }
+    if let Some(header) = page.header("content-language") {
+        hints.extend(header.split(','));
+    }
+    if let Some(metadata) = &page.metadata {
+        hints.extend(metadata.open_graph.locale.as_deref());
+    }
+
+    let text = match (&page.article, &page.document) {
+        (Some(article), _) => Some(article.text.as_str()),
+        (None, Some(document)) => document.text(),
+        (None, _) => None,
+    };
+
+    page.language = detect_language(text, &hints);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FRENCH: &str = "Le crawler parcourt les pages du site, lit leur \
+        contenu et suit les liens qu'elles contiennent afin de construire \
+        un index complet de tous les documents qui y sont publiés.";
+
+    #[test]
+    fn language_detection() {
+        let language = detect_language(Some(FRENCH), &[]).unwrap();
+        assert_eq!(language.code, "fr");
+        assert_eq!(language.source, LanguageSource::Detected);
+
+        // A declaration confirmed by the text is more certain.
+        let confirmed =
+            detect_language(Some(FRENCH), &["en", "fr-CA"]).unwrap();
+        assert_eq!(confirmed.code, "fr");
+        assert_eq!(confirmed.source, LanguageSource::Both);
+        assert!(confirmed.confidence >= language.confidence);
+
+        // A wrong declaration is overridden by a reliable detection.
+        let overridden = detect_language(Some(FRENCH), &["de"]).unwrap();
+        assert_eq!(overridden.code, "fr");
+        assert_eq!(overridden.source, LanguageSource::Detected);
+
+        // Too short a text is not enough to override it.
+        let declared = detect_language(Some("OK"), &["de_CH"]).unwrap();
+        assert_eq!(declared.code, "de");
+        assert_eq!(declared.source, LanguageSource::Declared);
+        assert_eq!(declared.confidence, DECLARED_CONFIDENCE);
+
+        assert_eq!(detect_language(None, &["x-default", "und"]), None);
+        assert_eq!(detect_language(Some(""), &[]), None);
+    }
+
+    #[test]
+    fn declared_languages() {
+        assert_eq!(primary_language("en-GB").as_deref(), Some("en"));
+        assert_eq!(primary_language(" pt_BR ").as_deref(), Some("pt"));
+        assert_eq!(primary_language("ZH-Hant-TW").as_deref(), Some("zh"));
+        assert_eq!(primary_language("iw").as_deref(), Some("he"));
+        assert_eq!(primary_language("no").as_deref(), Some("nb"));
+        assert_eq!(primary_language("fra").as_deref(), Some("fr"));
+        assert_eq!(primary_language("br").as_deref(), Some("br"));
+        for tag in ["", "und", "x-default", "*", "12"] {
+            assert_eq!(primary_language(tag), None);
+        }
+    }
+}
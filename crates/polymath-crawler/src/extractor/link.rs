@@ -1,43 +1,298 @@
-use regex_lite::Regex;
+//! Link extraction.
 
-lazy_static! {
-    static ref URL: Regex = Regex::new(r"https?:\/\/(www\.)?[-a-zA-Z0-9@:%._\+~#=]{1,256}\.[a-zA-Z0-9()]{1,6}\b([-a-zA-Z0-9()@:%_\+.~#?&//=]*)").unwrap();
+use polymath_error::{Error, ErrorType::Scraper, ScraperError};
+use scraper::{ElementRef, Html, Selector};
+use url::Url;
+
+/// Kind of resource a [Link] points to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    /// Navigation link (`<a>`, `<area>`).
+    Anchor,
+    /// Resource linked to the document (`<link>`, `<iframe>`, `<frame>`).
+    Resource,
+    /// Embedded media (`<img>`, `<source>`).
+    Media,
+}
+
+/// Link found in a document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Link {
+    /// Absolute URL of the link.
+    pub url: Url,
+    /// Kind of the link.
+    pub kind: LinkKind,
+    /// Text describing the link: content of an anchor, or `alt` attribute
+    /// of an area or an image.
+    pub text: Option<String>,
+    /// Lowercased values of the `rel` attribute.
+    pub rel: Vec<String>,
+    /// `title` attribute of the element.
+    pub title: Option<String>,
+    /// `<figcaption>` of the `<figure>` containing an image.
+    pub caption: Option<String>,
 }
 
-pub(crate) fn find_all_links(content: &str) -> Vec<String> {
-    let mut urls = Vec::new();
+/// Extracts every link of an HTML page.
+///
+/// Relative URLs are resolved against the `<base href>` of the document if
+/// any, or `url` otherwise. Links using another scheme than HTTP(S) (such as
+/// `mailto:` or `javascript:`) are ignored.
+pub fn extract_links(body: &str, url: &Url) -> Result<Vec<Link>, Error> {
+    links(&Html::parse_document(body), url)
+}
+
+/// Extracts every link of a parsed HTML page, see [extract_links].
+pub(crate) fn links(document: &Html, url: &Url) -> Result<Vec<Link>, Error> {
+    let base_selector = selector("base[href]")?;
+    let figcaption_selector = selector("figcaption")?;
+    let selector = selector(
+        "a[href], area[href], link[href], iframe[src], frame[src], img[src], \
+         img[srcset], source[srcset]",
+    )?;
+
+    let base = document
+        .select(&base_selector)
+        .next()
+        .and_then(|base| base.value().attr("href"))
+        .and_then(|href| url.join(href.trim()).ok())
+        .unwrap_or_else(|| url.clone());
+
+    let mut links = Vec::new();
+
+    for element in document.select(&selector) {
+        let value = element.value();
+        let rel: Vec<String> = value
+            .attr("rel")
+            .map(|rel| rel.split_whitespace().map(str::to_lowercase).collect())
+            .unwrap_or_default();
+
+        let title = value
+            .attr("title")
+            .map(str::trim)
+            .filter(|title| !title.is_empty())
+            .map(str::to_owned);
+
+        let (kind, text, targets) = match value.name() {
+            "a" => (
+                LinkKind::Anchor,
+                text_of(&element),
+                vec![value.attr("href")],
+            ),
+            "area" => (
+                LinkKind::Anchor,
+                value.attr("alt").map(str::to_owned),
+                vec![value.attr("href")],
+            ),
+            "link" => (LinkKind::Resource, None, vec![value.attr("href")]),
+            "iframe" | "frame" => {
+                (LinkKind::Resource, None, vec![value.attr("src")])
+            },
+            _ => {
+                let mut targets = vec![value.attr("src")];
+                targets.extend(
+                    value.attr("srcset").map(srcset).unwrap_or_default(),
+                );
+                (
+                    LinkKind::Media,
+                    value.attr("alt").map(str::to_owned),
+                    targets,
+                )
+            },
+        };
+
+        let caption = (kind == LinkKind::Media)
+            .then(|| caption_of(&element, &figcaption_selector))
+            .flatten();
+
+        for target in targets.into_iter().flatten() {
+            let Ok(resolved) = base.join(target.trim()) else {
+                continue;
+            };
+
+            if resolved.scheme() != "http" && resolved.scheme() != "https" {
+                continue;
+            }
 
-    for mat in URL.find_iter(content) {
-        urls.push(mat.as_str().to_string());
+            links.push(Link {
+                url: resolved,
+                kind,
+                text: text.clone(),
+                rel: rel.clone(),
+                title: title.clone(),
+                caption: caption.clone(),
+            });
+        }
     }
 
-    urls
+    Ok(links)
+}
+
+fn selector(selectors: &str) -> Result<Selector, Error> {
+    Selector::parse(selectors).map_err(|_| {
+        Error::new(
+            Scraper(ScraperError::Selector),
+            None,
+            Some("while getting links".to_owned()),
+        )
+    })
+}
+
+/// Whitespace-normalized text of an element, if not empty.
+fn text_of(element: &ElementRef) -> Option<String> {
+    let text = element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    (!text.is_empty()).then_some(text)
+}
+
+/// Caption of the closest `<figure>` containing an element, if any.
+fn caption_of(element: &ElementRef, figcaption: &Selector) -> Option<String> {
+    element
+        .ancestors()
+        .filter_map(ElementRef::wrap)
+        .find(|ancestor| ancestor.value().name() == "figure")?
+        .select(figcaption)
+        .next()
+        .and_then(|caption| text_of(&caption))
+}
+
+/// URLs of a [`srcset`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/img#srcset)
+/// attribute, without their width or density descriptors.
+fn srcset(value: &str) -> Vec<Option<&str>> {
+    value
+        .split(',')
+        .filter_map(|candidate| candidate.split_whitespace().next())
+        .map(Some)
+        .collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn urls(links: &[Link]) -> Vec<&str> {
+        links.iter().map(|link| link.url.as_str()).collect()
+    }
+
     #[test]
     fn links_extraction() {
         let html = r#"
         <body>
         <p>La vie seule... c'est dur</p>
-        <p>Mais avec notre ami imaginaire, c'est mieux : <a href="https://www.gravitalia.com/">cliquez ici</a></p>
+        <p>Mais avec notre ami imaginaire, c'est mieux : <a href="https://www.gravitalia.com/">cliquez   ici</a></p>
         <h1>Voir nos actualités : http://news.gravitalia.com/</h1>
+        <a href="/about" rel="Nofollow UGC">À propos</a>
+        <a href="../x.html"><img src="logo.png" alt="Logo"></a>
+        <a href="mailto:contact@gravitalia.com">Contact</a>
         <script>
         window.open("https://youtu.be/yG-1v-_1NVM", "_blank");
         </script>
         </body>
         "#;
 
+        let url = Url::parse("https://gravitalia.com/blog/post/").unwrap();
+        let links = extract_links(html, &url).unwrap();
+
         assert_eq!(
+            urls(&links),
             vec![
                 "https://www.gravitalia.com/",
-                "http://news.gravitalia.com/",
-                "https://youtu.be/yG-1v-_1NVM"
-            ],
-            find_all_links(html)
+                "https://gravitalia.com/about",
+                "https://gravitalia.com/blog/x.html",
+                "https://gravitalia.com/blog/post/logo.png",
+            ]
+        );
+        assert_eq!(links[0].text.as_deref(), Some("cliquez ici"));
+        assert_eq!(links[1].rel, vec!["nofollow", "ugc"]);
+        assert_eq!(links[2].text, None);
+        assert_eq!(links[3].kind, LinkKind::Media);
+        assert_eq!(links[3].text.as_deref(), Some("Logo"));
+    }
+
+    #[test]
+    fn links_kinds() {
+        let html = r#"
+        <head>
+        <base href="https://cdn.gravitalia.com/assets/">
+        <link rel="stylesheet" href="style.css">
+        </head>
+        <body>
+        <map><area href="/map" alt="Carte"></map>
+        <iframe src="embed.html"></iframe>
+        <picture>
+        <source srcset="a.webp 1x, b.webp 2x">
+        <img src="a.png" srcset="a-480.png 480w, a-800.png 800w">
+        </picture>
+        </body>
+        "#;
+
+        let url = Url::parse("https://gravitalia.com/").unwrap();
+        let links = extract_links(html, &url).unwrap();
+
+        assert_eq!(
+            links
+                .iter()
+                .map(|link| (link.url.as_str(), link.kind))
+                .collect::<Vec<_>>(),
+            vec![
+                (
+                    "https://cdn.gravitalia.com/assets/style.css",
+                    LinkKind::Resource
+                ),
+                ("https://cdn.gravitalia.com/map", LinkKind::Anchor),
+                (
+                    "https://cdn.gravitalia.com/assets/embed.html",
+                    LinkKind::Resource
+                ),
+                ("https://cdn.gravitalia.com/assets/a.webp", LinkKind::Media),
+                ("https://cdn.gravitalia.com/assets/b.webp", LinkKind::Media),
+                ("https://cdn.gravitalia.com/assets/a.png", LinkKind::Media),
+                (
+                    "https://cdn.gravitalia.com/assets/a-480.png",
+                    LinkKind::Media
+                ),
+                (
+                    "https://cdn.gravitalia.com/assets/a-800.png",
+                    LinkKind::Media
+                ),
+            ]
+        );
+        assert_eq!(links[0].rel, vec!["stylesheet"]);
+        assert_eq!(links[1].text.as_deref(), Some("Carte"));
+
+        let html = r#"<html><frameset><frame src="menu.html"></frameset>"#;
+        assert_eq!(
+            urls(&extract_links(html, &url).unwrap()),
+            vec!["https://gravitalia.com/menu.html"]
+        );
+    }
+
+    #[test]
+    fn links_context() {
+        let html = r#"
+        <figure>
+        <a href="/tour" title=" Visite ">Tour</a>
+        <img src="tour.jpg" alt="La tour" title="Tour Eiffel">
+        <figcaption>La tour Eiffel   de nuit</figcaption>
+        </figure>
+        <img src="logo.png">
+        "#;
+
+        let url = Url::parse("https://gravitalia.com/").unwrap();
+        let links = extract_links(html, &url).unwrap();
+
+        assert_eq!(links[0].title.as_deref(), Some("Visite"));
+        assert_eq!(links[0].caption, None);
+        assert_eq!(links[1].text.as_deref(), Some("La tour"));
+        assert_eq!(links[1].title.as_deref(), Some("Tour Eiffel"));
+        assert_eq!(links[1].caption.as_deref(), Some("La tour Eiffel de nuit"));
+        assert_eq!(
+            (links[2].title.as_ref(), links[2].caption.as_ref()),
+            (None, None)
         );
     }
 }
@@ -1,7 +1,13 @@
 //! Meta tag extraction.
 
+use std::time::SystemTime;
+
 use polymath_error::{Error, ErrorType::Scraper, ScraperError};
 use scraper::{Html, Selector};
+use url::Url;
+
+use super::canonical::Alternate;
+use super::date;
 
 /// Representation of data contained in a
 /// [`<meta>`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/meta) tag.
@@ -25,21 +31,358 @@ pub struct Meta {
     pub scheme: Option<String>,
 }
 
+/// Directives given to crawlers by
+/// [`<meta name="robots">`](https://developers.google.com/search/docs/crawling-indexing/robots-meta-tag)
+/// tags.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RobotsDirectives {
+    /// The page must not be indexed.
+    pub noindex: bool,
+    /// Links of the page must not be followed.
+    pub nofollow: bool,
+}
+
+impl RobotsDirectives {
+    /// Reads the directives of every `robots` meta tag.
+    pub fn from_meta(metas: &[Meta]) -> Self {
+        metas
+            .iter()
+            .filter(|meta| {
+                meta.name
+                    .as_deref()
+                    .is_some_and(|name| name.eq_ignore_ascii_case("robots"))
+            })
+            .filter_map(|meta| meta.content.as_deref())
+            .flat_map(|content| content.split(','))
+            .fold(Self::default(), |mut directives, directive| {
+                match directive.trim().to_lowercase().as_str() {
+                    "noindex" => directives.noindex = true,
+                    "nofollow" => directives.nofollow = true,
+                    "none" => {
+                        directives.noindex = true;
+                        directives.nofollow = true;
+                    },
+                    _ => {},
+                }
+                directives
+            })
+    }
+}
+
+/// Image or video of an [OpenGraph](https://ogp.me/#structured) object.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OpenGraphMedia {
+    /// `og:image` or `og:video`.
+    pub url: Url,
+    /// `og:image:secure_url` or `og:video:secure_url`.
+    pub secure_url: Option<Url>,
+    /// Media type, such as `image/png`.
+    pub content_type: Option<String>,
+    /// Width, in pixels.
+    pub width: Option<u32>,
+    /// Height, in pixels.
+    pub height: Option<u32>,
+    /// Description of the media.
+    pub alt: Option<String>,
+}
+
+/// [OpenGraph](https://ogp.me/) properties of a page.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OpenGraph {
+    /// `og:title`.
+    pub title: Option<String>,
+    /// `og:type`, such as `website` or `article`.
+    pub kind: Option<String>,
+    /// `og:url`, the canonical URL of the object.
+    pub url: Option<Url>,
+    /// `og:description`.
+    pub description: Option<String>,
+    /// `og:site_name`.
+    pub site_name: Option<String>,
+    /// `og:locale`, such as `fr_FR`.
+    pub locale: Option<String>,
+    /// `og:locale:alternate`.
+    pub alternate_locales: Vec<String>,
+    /// `og:image` tags, in order, with their structured properties.
+    pub images: Vec<OpenGraphMedia>,
+    /// `og:video` tags, in order, with their structured properties.
+    pub videos: Vec<OpenGraphMedia>,
+}
+
+/// [Twitter Card](https://developer.x.com/en/docs/twitter-for-websites/cards/overview/markup)
+/// properties of a page.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TwitterCard {
+    /// `twitter:card`, such as `summary_large_image`.
+    pub card: Option<String>,
+    /// `twitter:site`, the account of the website.
+    pub site: Option<String>,
+    /// `twitter:creator`, the account of the author.
+    pub creator: Option<String>,
+    /// `twitter:title`.
+    pub title: Option<String>,
+    /// `twitter:description`.
+    pub description: Option<String>,
+    /// `twitter:image`.
+    pub image: Option<Url>,
+    /// `twitter:image:alt`.
+    pub image_alt: Option<String>,
+}
+
+/// Metadata declared in the `<head>` of a page.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PageMetadata {
+    /// Content of the `<title>` tag.
+    pub title: Option<String>,
+    /// `description` meta tag.
+    pub description: Option<String>,
+    /// `keywords` meta tag, split on commas.
+    pub keywords: Vec<String>,
+    /// `<link rel="canonical">` of the page.
+    pub canonical: Option<Url>,
+    /// `<link rel="alternate" hreflang>` translations of the page.
+    pub alternates: Vec<Alternate>,
+    /// `lang` attribute of the `<html>` tag or, failing that, the
+    /// `Content-Language` of a `http-equiv` meta tag.
+    pub language: Option<String>,
+    /// `author` meta tag, or `article:author` property.
+    pub author: Option<String>,
+    /// `article:published_time` property, or `date` meta tag.
+    pub published_at: Option<SystemTime>,
+    /// `article:modified_time` or `og:updated_time` property.
+    pub modified_at: Option<SystemTime>,
+    /// Directives of the `robots` meta tags.
+    pub robots: RobotsDirectives,
+    /// OpenGraph properties.
+    pub open_graph: OpenGraph,
+    /// Twitter Card properties.
+    pub twitter: TwitterCard,
+}
+
+impl PageMetadata {
+    /// Reads the metadata of meta tags. Relative URLs are resolved against
+    /// `url`.
+    ///
+    /// Properties are read from the `property` attribute, or the `name` one
+    /// as many pages declare OpenGraph properties with it. The
+    /// [title](PageMetadata::title), [canonical](PageMetadata::canonical) URL
+    /// and `lang` attribute are not meta tags, see [extract_metadata].
+    pub fn from_meta(metas: &[Meta], url: &Url) -> Self {
+        let mut metadata = PageMetadata {
+            robots: RobotsDirectives::from_meta(metas),
+            ..Default::default()
+        };
+        let og = &mut metadata.open_graph;
+        let twitter = &mut metadata.twitter;
+        let mut published_at = None;
+        let mut modified_at = None;
+
+        for meta in metas {
+            let Some(content) = meta
+                .content
+                .as_deref()
+                .map(str::trim)
+                .filter(|content| !content.is_empty())
+            else {
+                continue;
+            };
+            let text = || Some(content.to_owned());
+            let link = || url.join(content).ok();
+
+            if let Some(http_equiv) = &meta.http_equiv {
+                if http_equiv.eq_ignore_ascii_case("content-language") {
+                    metadata.language =
+                        metadata.language.take().or_else(|| {
+                            content
+                                .split(',')
+                                .next()
+                                .map(|lang| lang.trim().to_owned())
+                        });
+                }
+                continue;
+            }
+            let Some(key) = meta.property.as_deref().or(meta.name.as_deref())
+            else {
+                continue;
+            };
+
+            let key = key.trim().to_lowercase();
+            match key.as_str() {
+                "description" => metadata.description = text(),
+                "keywords" => {
+                    metadata.keywords = content
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|keyword| !keyword.is_empty())
+                        .map(str::to_owned)
+                        .collect();
+                },
+                "author" => metadata.author = text(),
+                "article:author" => {
+                    metadata.author = metadata.author.take().or_else(text);
+                },
+                "article:published_time" => published_at = text(),
+                "date" => published_at = published_at.take().or_else(text),
+                "article:modified_time" => modified_at = text(),
+                "og:updated_time" => {
+                    modified_at = modified_at.take().or_else(text);
+                },
+
+                "og:title" => og.title = text(),
+                "og:type" => og.kind = text(),
+                "og:url" => og.url = link(),
+                "og:description" => og.description = text(),
+                "og:site_name" => og.site_name = text(),
+                "og:locale" => og.locale = text(),
+                "og:locale:alternate" => {
+                    og.alternate_locales.push(content.to_owned());
+                },
+                "og:image" | "og:image:url" | "og:video" | "og:video:url" => {
+                    let list = if key.contains("image") {
+                        &mut og.images
+                    } else {
+                        &mut og.videos
+                    };
+                    // `og:image:url` may repeat the `og:image` before it.
+                    let repeated = |url: &Url| {
+                        key.ends_with(":url") &&
+                            list.last().is_some_and(|last| last.url == *url)
+                    };
+                    if let Some(url) = link().filter(|url| !repeated(url)) {
+                        list.push(OpenGraphMedia {
+                            url,
+                            secure_url: None,
+                            content_type: None,
+                            width: None,
+                            height: None,
+                            alt: None,
+                        });
+                    }
+                },
+                key if key.starts_with("og:image:") ||
+                    key.starts_with("og:video:") =>
+                {
+                    // Structured properties describe the last image or video.
+                    let (list, property) = match key.split_at(9) {
+                        ("og:image:", property) => (&mut og.images, property),
+                        (_, property) => (&mut og.videos, property),
+                    };
+                    let Some(current) = list.last_mut() else {
+                        continue;
+                    };
+                    match property {
+                        "secure_url" => current.secure_url = link(),
+                        "type" => current.content_type = text(),
+                        "width" => current.width = content.parse().ok(),
+                        "height" => current.height = content.parse().ok(),
+                        "alt" => current.alt = text(),
+                        _ => {},
+                    }
+                },
+
+                "twitter:card" => twitter.card = text(),
+                "twitter:site" => twitter.site = text(),
+                "twitter:creator" => twitter.creator = text(),
+                "twitter:title" => twitter.title = text(),
+                "twitter:description" => twitter.description = text(),
+                "twitter:image" | "twitter:image:src" => twitter.image = link(),
+                "twitter:image:alt" => twitter.image_alt = text(),
+                _ => {},
+            }
+        }
+
+        metadata.published_at =
+            published_at.as_deref().and_then(date::parse_w3c);
+        metadata.modified_at = modified_at.as_deref().and_then(date::parse_w3c);
+        metadata
+    }
+}
+
 /// Extracts the [`<meta>`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/meta)
 /// tags contained in a complete HTML page.
 pub fn extract_meta_tags(body: &str) -> Result<Vec<Meta>, Error> {
-    let mut metas: Vec<Meta> = Vec::new();
+    meta_tags(&Html::parse_document(body))
+}
 
+/// Extracts the text of the
+/// [`<title>`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/title)
+/// tag of a complete HTML page, with whitespace collapsed.
+pub fn extract_title(body: &str) -> Result<Option<String>, Error> {
+    title(&Html::parse_document(body))
+}
+
+/// Extracts the [metadata](PageMetadata) of a complete HTML page, from its
+/// meta tags, `<title>`, canonical and alternate `<link>` tags and `lang`
+/// attribute.
+///
+/// Relative URLs are resolved against `url`.
+pub fn extract_metadata(body: &str, url: &Url) -> Result<PageMetadata, Error> {
     let document = Html::parse_document(body);
-    let selector = Selector::parse("meta").map_err(|_| {
+    metadata(&document, &meta_tags(&document)?, url)
+}
+
+/// Extracts the metadata of a parsed HTML page, given its meta tags, see
+/// [extract_metadata].
+pub(crate) fn metadata(
+    document: &Html,
+    metas: &[Meta],
+    url: &Url,
+) -> Result<PageMetadata, Error> {
+    let mut metadata = PageMetadata::from_meta(metas, url);
+    metadata.title = title(document)?;
+    for link in document.select(&selector("link[rel][href]", "links")?) {
+        let link = link.value();
+        let has_rel = |name: &str| {
+            link.attr("rel")
+                .unwrap_or_default()
+                .split_whitespace()
+                .any(|rel| rel.eq_ignore_ascii_case(name))
+        };
+        let Some(target) = link
+            .attr("href")
+            .and_then(|href| url.join(href.trim()).ok())
+        else {
+            continue;
+        };
+
+        if has_rel("canonical") && metadata.canonical.is_none() {
+            metadata.canonical = Some(target);
+        } else if let Some(language) = link.attr("hreflang") {
+            if has_rel("alternate") {
+                metadata.alternates.push(Alternate {
+                    language: language.trim().to_owned(),
+                    url: target,
+                });
+            }
+        }
+    }
+    if let Some(lang) = document
+        .select(&selector("html[lang]", "language")?)
+        .next()
+        .and_then(|html| html.value().attr("lang"))
+        .map(str::trim)
+        .filter(|lang| !lang.is_empty())
+    {
+        metadata.language = Some(lang.to_owned());
+    }
+
+    Ok(metadata)
+}
+
+fn selector(selectors: &str, target: &str) -> Result<Selector, Error> {
+    Selector::parse(selectors).map_err(|_| {
         Error::new(
             Scraper(ScraperError::Selector),
             None,
-            Some("while getting meta tags".to_owned()),
+            Some(format!("while getting {}", target)),
         )
-    })?;
+    })
+}
 
-    for element in document.select(&selector) {
+pub(crate) fn meta_tags(document: &Html) -> Result<Vec<Meta>, Error> {
+    let mut metas: Vec<Meta> = Vec::new();
+
+    for element in document.select(&selector("meta", "meta tags")?) {
         metas.push(Meta {
             name: element.value().attr("name").map(str::to_owned),
             property: element.value().attr("property").map(str::to_owned),
@@ -53,8 +396,24 @@ pub fn extract_meta_tags(body: &str) -> Result<Vec<Meta>, Error> {
     Ok(metas)
 }
 
+fn title(document: &Html) -> Result<Option<String>, Error> {
+    Ok(document
+        .select(&selector("title", "title")?)
+        .next()
+        .map(|title| {
+            title
+                .text()
+                .flat_map(str::split_whitespace)
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .filter(|title| !title.is_empty()))
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
     use super::*;
 
     #[test]
@@ -94,4 +453,196 @@ mod tests {
             extract_meta_tags(html).unwrap()
         )
     }
+
+    #[test]
+    fn title_extraction() {
+        let html = r#"<html><head><title>
+            RIP  mon
+            coeur</title></head><body><title>Autre</title></body></html>"#;
+        assert_eq!(
+            extract_title(html).unwrap(),
+            Some("RIP mon coeur".to_owned())
+        );
+
+        assert_eq!(extract_title("<title> </title>").unwrap(), None);
+        assert_eq!(extract_title("<p>Pas de titre</p>").unwrap(), None);
+    }
+
+    #[test]
+    fn robots_directives() {
+        let html = r#"
+        <meta name="description" content="nofollow">
+        <meta name="ROBOTS" content="index, NoFollow">
+        "#;
+        assert_eq!(
+            RobotsDirectives::from_meta(&extract_meta_tags(html).unwrap()),
+            RobotsDirectives {
+                noindex: false,
+                nofollow: true
+            }
+        );
+
+        let html = r#"<meta name="robots" content="none">"#;
+        assert_eq!(
+            RobotsDirectives::from_meta(&extract_meta_tags(html).unwrap()),
+            RobotsDirectives {
+                noindex: true,
+                nofollow: true
+            }
+        );
+    }
+
+    #[test]
+    fn metadata_extraction() {
+        let html = r#"
+        <!DOCTYPE html><html lang="fr-FR"><head>
+        <title>Le budget 2024</title>
+        <meta http-equiv="Content-Language" content="de">
+        <meta name="description" content="Le Conseil municipal vote le budget.">
+        <meta name="keywords" content="budget, mairie, , impôts">
+        <meta name="author" content="Ada">
+        <meta name="robots" content="noindex">
+        <link rel="alternate stylesheet" href="/a.css">
+        <link rel="Canonical" href="/budget-2024">
+        <link rel="alternate" hreflang="en" href="/en/budget-2024">
+        <link rel="alternate" hreflang="x-default" href="/budget-2024">
+        <link rel="canonical" href="/autre">
+        <meta property="og:title" content="Budget 2024">
+        <meta property="og:type" content="article">
+        <meta property="og:url" content="https://news.gravitalia.com/budget-2024">
+        <meta property="og:locale" content="fr_FR">
+        <meta property="og:locale:alternate" content="en_GB">
+        <meta property="og:image" content="/cover.png">
+        <meta property="og:image:url" content="/cover.png">
+        <meta property="og:image:width" content="1200">
+        <meta property="og:image:height" content="630">
+        <meta property="og:image:alt" content="La mairie">
+        <meta name="og:image" content="https://cdn.gravitalia.com/square.png">
+        <meta property="og:image:type" content="image/png">
+        <meta property="og:video" content="/video.mp4">
+        <meta property="og:video:width" content="640">
+        <meta property="article:published_time" content="2024-01-31T12:00:00+01:00">
+        <meta property="article:modified_time" content="2024-02-01">
+        <meta name="twitter:card" content="summary_large_image">
+        <meta name="twitter:site" content="@gravitalia">
+        <meta name="twitter:image" content="/card.png">
+        <meta name="twitter:image:alt" content="  ">
+        </head></html>
+        "#;
+        let url = Url::parse("https://news.gravitalia.com/a?id=1").unwrap();
+        let image = |url: &str| OpenGraphMedia {
+            url: Url::parse(url).unwrap(),
+            secure_url: None,
+            content_type: None,
+            width: None,
+            height: None,
+            alt: None,
+        };
+
+        let metadata = extract_metadata(html, &url).unwrap();
+
+        assert_eq!(metadata.title.as_deref(), Some("Le budget 2024"));
+        assert_eq!(
+            metadata.description.as_deref(),
+            Some("Le Conseil municipal vote le budget.")
+        );
+        assert_eq!(metadata.keywords, vec!["budget", "mairie", "impôts"]);
+        assert_eq!(
+            metadata.canonical.as_ref().map(Url::as_str),
+            Some("https://news.gravitalia.com/budget-2024")
+        );
+        assert_eq!(
+            metadata
+                .alternates
+                .iter()
+                .map(|alternate| (
+                    alternate.language.as_str(),
+                    alternate.url.as_str()
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                ("en", "https://news.gravitalia.com/en/budget-2024"),
+                ("x-default", "https://news.gravitalia.com/budget-2024"),
+            ]
+        );
+        assert_eq!(metadata.language.as_deref(), Some("fr-FR"));
+        assert_eq!(metadata.author.as_deref(), Some("Ada"));
+        assert_eq!(
+            metadata.published_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_706_698_800))
+        );
+        assert_eq!(
+            metadata.modified_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_706_745_600))
+        );
+        assert!(metadata.robots.noindex);
+
+        let og = &metadata.open_graph;
+        assert_eq!(og.title.as_deref(), Some("Budget 2024"));
+        assert_eq!(og.kind.as_deref(), Some("article"));
+        assert_eq!(
+            og.url.as_ref().map(Url::as_str),
+            Some("https://news.gravitalia.com/budget-2024")
+        );
+        assert_eq!(og.locale.as_deref(), Some("fr_FR"));
+        assert_eq!(og.alternate_locales, vec!["en_GB"]);
+        assert_eq!(
+            og.images,
+            vec![
+                OpenGraphMedia {
+                    width: Some(1200),
+                    height: Some(630),
+                    alt: Some("La mairie".to_owned()),
+                    ..image("https://news.gravitalia.com/cover.png")
+                },
+                OpenGraphMedia {
+                    content_type: Some("image/png".to_owned()),
+                    ..image("https://cdn.gravitalia.com/square.png")
+                },
+            ]
+        );
+        assert_eq!(
+            og.videos,
+            vec![OpenGraphMedia {
+                width: Some(640),
+                ..image("https://news.gravitalia.com/video.mp4")
+            }]
+        );
+
+        let twitter = &metadata.twitter;
+        assert_eq!(twitter.card.as_deref(), Some("summary_large_image"));
+        assert_eq!(twitter.site.as_deref(), Some("@gravitalia"));
+        assert_eq!(
+            twitter.image.as_ref().map(Url::as_str),
+            Some("https://news.gravitalia.com/card.png")
+        );
+        assert_eq!(twitter.image_alt, None);
+    }
+
+    #[test]
+    fn metadata_fallbacks() {
+        let html = r#"
+        <meta http-equiv="content-language" content="de-CH, fr">
+        <meta property="og:image:width" content="100">
+        <meta property="article:author" content="https://gravitalia.com/ada">
+        <meta name="date" content="2024-01-31">
+        <meta property="og:updated_time" content="demain">
+        "#;
+        let url = Url::parse("https://gravitalia.com/").unwrap();
+
+        let metadata = extract_metadata(html, &url).unwrap();
+
+        assert_eq!(metadata.language.as_deref(), Some("de-CH"));
+        assert_eq!(
+            metadata.author.as_deref(),
+            Some("https://gravitalia.com/ada")
+        );
+        assert_eq!(
+            metadata.published_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_706_659_200))
+        );
+        assert_eq!(metadata.modified_at, None);
+        assert!(metadata.open_graph.images.is_empty());
+        assert_eq!(metadata.canonical, None);
+    }
 }
@@ -1,4 +1,89 @@
-//! HTML, CSS extraction.
+//! HTML, CSS, PDF, office document and image extraction.
 
-pub(crate) mod link;
+use polymath_error::Error;
+
+use crate::content;
+use crate::page::FetchedPage;
+
+pub mod article;
+pub mod canonical;
+mod date;
+pub mod image;
+pub mod language;
+pub mod link;
 pub mod meta;
+pub mod office;
+pub mod pdf;
+pub mod structured;
+
+/// Text and metadata extracted from a resource other than an HTML page.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Document {
+    /// PDF document.
+    Pdf(pdf::Pdf),
+    /// Office document, such as a Word document or a presentation.
+    Office(office::Office),
+    /// Image, with the context of the page on which it was found.
+    Image(image::Image),
+}
+
+impl Document {
+    /// Title of the document, if any.
+    pub fn title(&self) -> Option<&str> {
+        match self {
+            Document::Pdf(pdf) => pdf.title.as_deref(),
+            Document::Office(office) => office.properties.title.as_deref(),
+            Document::Image(image) => image.context.title.as_deref(),
+        }
+    }
+
+    /// Text of the document. `None` for images.
+    pub fn text(&self) -> Option<&str> {
+        match self {
+            Document::Pdf(pdf) => Some(&pdf.text),
+            Document::Office(office) => Some(&office.text),
+            Document::Image(_) => None,
+        }
+    }
+
+    /// Links found in the document.
+    pub fn links(&self) -> &[link::Link] {
+        match self {
+            Document::Pdf(pdf) => &pdf.links,
+            Document::Office(office) => &office.links,
+            Document::Image(_) => &[],
+        }
+    }
+}
+
+/// Extracts the content of a fetched resource other than an HTML page.
+/// `None` if its format is not supported.
+pub(crate) fn extract_document(
+    page: &FetchedPage,
+) -> Option<Result<Document, Error>> {
+    let url = &page.final_url;
+    let extension = content::extension(url);
+
+    if page.is_pdf() {
+        Some(pdf::extract_pdf(&page.body, url).map(Document::Pdf))
+    } else if office::is_office(
+        page.content_type.as_deref(),
+        extension.as_deref(),
+    ) {
+        Some(office::extract_office(&page.body, url).map(Document::Office))
+    } else if image::is_image(
+        page.content_type.as_deref(),
+        extension.as_deref(),
+    ) {
+        Some(image::extract_image(&page.body, url).map(|mut image| {
+            image.context = image::ImageContext::new(
+                page.link.as_ref(),
+                page.referrer.as_ref(),
+            );
+            Document::Image(image)
+        }))
+    } else {
+        None
+    }
+}
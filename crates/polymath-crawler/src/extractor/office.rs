@@ -0,0 +1,955 @@
+//! Office document extraction.
+//!
+//! Office Open XML (`.docx`, `.pptx`) and OpenDocument (`.odt`, `.odp`)
+//! files are zip archives of XML parts, read as such. Legacy Word (`.doc`)
+//! and PowerPoint (`.ppt`) files are only scraped for text.
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::sync::LazyLock;
+use std::time::SystemTime;
+
+use polymath_error::{Error, ErrorType::Scraper, ScraperError};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use regex_lite::Regex;
+use url::Url;
+use zip::ZipArchive;
+
+use super::date;
+use super::link::{Link, LinkKind};
+
+/// Maximum size of an uncompressed part of an archive, in bytes.
+const MAX_PART_SIZE: u64 = 32 * 1024 * 1024;
+
+/// Maximum size of all the uncompressed parts read from an archive, in
+/// bytes.
+const MAX_ARCHIVE_SIZE: u64 = 64 * 1024 * 1024;
+
+/// Maximum number of spaces an OpenDocument `<text:s>` element stands for.
+const MAX_SPACES: usize = 1024;
+
+/// Minimum number of characters of a text run scraped from a legacy file.
+const MIN_RUN: usize = 4;
+
+/// Media types of office documents.
+const MEDIA_TYPES: [&str; 6] = [
+    "application/msword",
+    "application/vnd.ms-powerpoint",
+    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+    "application/vnd.openxmlformats-officedocument.presentationml.presentation",
+    "application/vnd.oasis.opendocument.text",
+    "application/vnd.oasis.opendocument.presentation",
+];
+
+/// Extensions of office documents.
+const EXTENSIONS: [&str; 6] = ["doc", "docx", "ppt", "pptx", "odt", "odp"];
+
+/// URLs in the text of legacy documents.
+static URL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"https?://[^\s"<>]+"#).unwrap());
+
+/// Format of an office document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfficeFormat {
+    /// Office Open XML document (`.docx`).
+    Docx,
+    /// Office Open XML presentation (`.pptx`).
+    Pptx,
+    /// OpenDocument text (`.odt`).
+    Odt,
+    /// OpenDocument presentation (`.odp`).
+    Odp,
+    /// Legacy Word document (`.doc`).
+    Doc,
+    /// Legacy PowerPoint presentation (`.ppt`).
+    Ppt,
+}
+
+/// Properties of an office document, such as its title and author.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Properties {
+    /// Title of the document.
+    pub title: Option<String>,
+    /// Subject of the document.
+    pub subject: Option<String>,
+    /// Initial author of the document.
+    pub author: Option<String>,
+    /// Keywords describing the document.
+    pub keywords: Vec<String>,
+    /// Description of the document.
+    pub description: Option<String>,
+    /// When the document was created.
+    pub created_at: Option<SystemTime>,
+    /// When the document was last modified.
+    pub modified_at: Option<SystemTime>,
+}
+
+/// Text, properties and links of an office document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Office {
+    /// Format of the document.
+    pub format: OfficeFormat,
+    /// Text of the document, one paragraph per line. Slides of a
+    /// presentation are separated by a blank line.
+    pub text: String,
+    /// Text of each slide of a presentation, in order. Empty for text
+    /// documents and legacy presentations.
+    pub slides: Vec<String>,
+    /// Document properties. Not read from legacy documents.
+    pub properties: Properties,
+    /// HTTP(S) hyperlinks of the document, with their text if any.
+    pub links: Vec<Link>,
+}
+
+/// Whether a response is an office document, from its media type or, for
+/// generic binary responses, the extension of its URL.
+pub(crate) fn is_office(
+    media_type: Option<&str>,
+    extension: Option<&str>,
+) -> bool {
+    match media_type {
+        Some(media_type) if media_type != "application/octet-stream" => {
+            MEDIA_TYPES.contains(&media_type)
+        },
+        _ => extension.is_some_and(|extension| EXTENSIONS.contains(&extension)),
+    }
+}
+
+/// Extracts the text, properties and hyperlinks of an office document. The
+/// format is recognized from the content of the document.
+///
+/// Relative hyperlinks are resolved against `url`.
+pub fn extract_office(body: &[u8], url: &Url) -> Result<Office, Error> {
+    if body.starts_with(b"PK\x03\x04") {
+        let mut archive = Archive {
+            zip: ZipArchive::new(Cursor::new(body))
+                .map_err(|e| invalid(url, Some(Box::new(e))))?,
+            budget: MAX_ARCHIVE_SIZE,
+        };
+
+        if let Some(mimetype) = part(&mut archive, "mimetype") {
+            match mimetype.trim() {
+                "application/vnd.oasis.opendocument.text" => {
+                    return extract_odf(&mut archive, OfficeFormat::Odt, url);
+                },
+                "application/vnd.oasis.opendocument.presentation" => {
+                    return extract_odf(&mut archive, OfficeFormat::Odp, url);
+                },
+                _ => {},
+            }
+        } else if archive.zip.index_for_name("word/document.xml").is_some() {
+            return extract_docx(&mut archive, url);
+        } else if archive.zip.index_for_name("ppt/presentation.xml").is_some() {
+            return extract_pptx(&mut archive, url);
+        }
+    } else if let Ok(mut file) = cfb::CompoundFile::open(Cursor::new(body)) {
+        for (stream, format) in [
+            ("/WordDocument", OfficeFormat::Doc),
+            ("/PowerPoint Document", OfficeFormat::Ppt),
+        ] {
+            let mut data = Vec::new();
+            if let Ok(mut stream) = file.open_stream(stream) {
+                stream
+                    .read_to_end(&mut data)
+                    .map_err(|e| invalid(url, Some(Box::new(e))))?;
+                return Ok(extract_legacy(&data, format, url));
+            }
+        }
+    }
+
+    Err(invalid(url, None))
+}
+
+fn invalid(
+    url: &Url,
+    cause: Option<Box<dyn std::error::Error + Send + Sync>>,
+) -> Error {
+    Error::new(
+        Scraper(ScraperError::InvalidDocument),
+        cause,
+        Some(format!("while reading office document {}", url)),
+    )
+}
+
+/// Zip archive of an office document, with the number of bytes which can
+/// still be decompressed from it.
+struct Archive<'a> {
+    zip: ZipArchive<Cursor<&'a [u8]>>,
+    budget: u64,
+}
+
+/// Content of a part of an archive, if any. `None` once
+/// [MAX_ARCHIVE_SIZE] bytes were read from the archive.
+fn part(archive: &mut Archive, name: &str) -> Option<String> {
+    if archive.budget == 0 {
+        return None;
+    }
+
+    let mut content = Vec::new();
+    let read = archive
+        .zip
+        .by_name(name)
+        .ok()?
+        .take(MAX_PART_SIZE.min(archive.budget))
+        .read_to_end(&mut content);
+    archive.budget -= content.len() as u64;
+    read.ok()?;
+
+    String::from_utf8(content).ok()
+}
+
+fn extract_docx(archive: &mut Archive, url: &Url) -> Result<Office, Error> {
+    let document =
+        part(archive, "word/document.xml").ok_or_else(|| invalid(url, None))?;
+    let text = XmlText::read(&document, &DOCX);
+    let targets = hyperlinks(archive, "word/_rels/document.xml.rels");
+
+    Ok(Office {
+        format: OfficeFormat::Docx,
+        text: text.sections.join("\n\n"),
+        slides: Vec::new(),
+        properties: ooxml_properties(archive),
+        links: text.links(url, Some(&targets)),
+    })
+}
+
+fn extract_pptx(archive: &mut Archive, url: &Url) -> Result<Office, Error> {
+    let presentation = part(archive, "ppt/presentation.xml")
+        .ok_or_else(|| invalid(url, None))?;
+    let relationships =
+        relationships(archive, "ppt/_rels/presentation.xml.rels");
+
+    let mut slides = Vec::new();
+    let mut links = Vec::new();
+    for id in attributes(&presentation, "sldId", "r:id") {
+        let Some(relationship) = relationships.get(&id) else {
+            continue;
+        };
+        let path =
+            format!("ppt/{}", relationship.target.trim_start_matches('/'));
+        let Some(slide) = part(archive, &path) else {
+            continue;
+        };
+
+        let (directory, name) = path.rsplit_once('/').unwrap_or(("", &path));
+        let rels = format!("{}/_rels/{}.rels", directory, name);
+        let text = XmlText::read(&slide, &PPTX);
+
+        links.extend(text.links(url, Some(&hyperlinks(archive, &rels))));
+        slides.push(text.sections.join("\n"));
+    }
+
+    Ok(Office {
+        format: OfficeFormat::Pptx,
+        text: slides.join("\n\n"),
+        slides,
+        properties: ooxml_properties(archive),
+        links,
+    })
+}
+
+fn extract_odf(
+    archive: &mut Archive,
+    format: OfficeFormat,
+    url: &Url,
+) -> Result<Office, Error> {
+    let content =
+        part(archive, "content.xml").ok_or_else(|| invalid(url, None))?;
+    let text = XmlText::read(&content, &ODF);
+    let properties = part(archive, "meta.xml")
+        .map(|meta| properties(&meta))
+        .unwrap_or_default();
+
+    let slides = if format == OfficeFormat::Odp {
+        text.sections.clone()
+    } else {
+        Vec::new()
+    };
+
+    Ok(Office {
+        format,
+        text: text.sections.join("\n\n"),
+        slides,
+        properties,
+        links: text.links(url, None),
+    })
+}
+
+/// Best-effort text of the main stream of a legacy document: runs of
+/// printable UTF-16 or Windows-1252 characters. Field codes, such as
+/// `HYPERLINK "https://gravitalia.com/"`, are only read for their links.
+fn extract_legacy(data: &[u8], format: OfficeFormat, url: &Url) -> Office {
+    let mut runs = Vec::new();
+    let mut fields = Vec::new();
+
+    // UTF-16, only for alphabetic scripts: random bytes would look like
+    // ideographs. Records may start at odd offsets.
+    for alignment in 0..2 {
+        let units: Vec<u16> = data[alignment.min(data.len())..]
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        let mut start = 0;
+
+        for (index, unit) in units.iter().chain([&0]).enumerate() {
+            // Misaligned ASCII reads as units ending with a zero byte.
+            let printable = *unit < 0x3000 &&
+                (*unit < 0x100 || *unit & 0xff != 0) &&
+                char::from_u32(u32::from(*unit))
+                    .is_some_and(|c| c == '\r' || c == '\t' || !c.is_control());
+            if printable {
+                continue;
+            }
+
+            let run = String::from_utf16_lossy(&units[start..index]);
+            if run.trim().chars().count() >= MIN_RUN {
+                // 0x13 starts a field, whose code ends with 0x14 or 0x15.
+                if start > 0 && units[start - 1] == 0x13 {
+                    fields.push(run);
+                } else {
+                    runs.push((alignment + start * 2, run));
+                }
+            }
+            start = index + 1;
+        }
+    }
+
+    // Single-byte text.
+    let mut start = 0;
+    for (index, byte) in data.iter().chain([&0]).enumerate() {
+        if matches!(byte, b'\t' | b'\r' | 0x20..=0x7e | 0xa0..=0xff) {
+            continue;
+        }
+
+        let run = &data[start..index];
+        if run.len() >= MIN_RUN && run.iter().any(u8::is_ascii_alphabetic) {
+            let (run, _) =
+                encoding_rs::WINDOWS_1252.decode_without_bom_handling(run);
+            runs.push((start, run.into_owned()));
+        }
+        start = index + 1;
+    }
+    runs.sort_by_key(|(offset, _)| *offset);
+
+    let text = runs
+        .iter()
+        .flat_map(|(_, run)| run.split('\r'))
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n");
+    let links = fields
+        .iter()
+        .chain([&text])
+        .flat_map(|text| URL.find_iter(text))
+        .filter_map(|target| link(url, target.as_str(), None))
+        .collect();
+
+    Office {
+        format,
+        text,
+        slides: Vec::new(),
+        properties: Properties::default(),
+        links,
+    }
+}
+
+/// Elements of an XML vocabulary holding text, by local name.
+#[derive(Debug)]
+struct Grammar {
+    /// Elements whose text is kept.
+    text: &'static [&'static str],
+    /// Elements ending with a line break.
+    paragraphs: &'static [&'static str],
+    /// Elements standing for a tab.
+    tab: &'static str,
+    /// Elements standing for a line break.
+    line_breaks: &'static [&'static str],
+    /// Elements standing for spaces, with their count as `c` attribute.
+    space: Option<&'static str>,
+    /// Elements splitting the text in slides.
+    section: Option<&'static str>,
+    /// Elements whose content is ignored.
+    ignored: &'static [&'static str],
+    /// Hyperlink element, and its attribute holding the target.
+    link: (&'static str, &'static str),
+}
+
+const DOCX: Grammar = Grammar {
+    text: &["t"],
+    paragraphs: &["p"],
+    tab: "tab",
+    line_breaks: &["br", "cr"],
+    space: None,
+    section: None,
+    // Paragraph properties hold tab stops.
+    ignored: &["pPr"],
+    link: ("hyperlink", "r:id"),
+};
+
+const PPTX: Grammar = Grammar {
+    text: &["t"],
+    paragraphs: &["p"],
+    tab: "tab",
+    line_breaks: &["br"],
+    space: None,
+    section: None,
+    ignored: &[],
+    link: ("hlinkClick", "r:id"),
+};
+
+const ODF: Grammar = Grammar {
+    text: &["p", "h"],
+    paragraphs: &["p", "h"],
+    tab: "tab",
+    line_breaks: &["line-break"],
+    space: Some("s"),
+    section: Some("page"),
+    ignored: &["notes"],
+    link: ("a", "xlink:href"),
+};
+
+/// Text of an XML part.
+#[derive(Debug, Default)]
+struct XmlText {
+    /// Text of each section, one paragraph per line.
+    sections: Vec<String>,
+    /// Hyperlink targets, with their text if any.
+    links: Vec<(String, Option<String>)>,
+}
+
+impl XmlText {
+    /// Reads the text of an XML part. Malformed XML ends the text early.
+    fn read(xml: &str, grammar: &Grammar) -> Self {
+        let mut reader = Reader::from_str(xml);
+        let mut result = XmlText::default();
+        let mut current = String::new();
+        let mut in_text = 0;
+        let mut ignored = 0;
+        let mut open_links: Vec<Option<(String, usize)>> = Vec::new();
+
+        while let Ok(event) = reader.read_event() {
+            match event {
+                Event::Start(element) => {
+                    let name = local_name(&element);
+                    if ignored > 0 || grammar.ignored.contains(&name.as_str()) {
+                        ignored += 1;
+                        continue;
+                    }
+
+                    if grammar.text.contains(&name.as_str()) {
+                        in_text += 1;
+                    }
+                    if name == grammar.link.0 {
+                        open_links.push(
+                            attribute(&element, grammar.link.1)
+                                .map(|target| (target, current.len())),
+                        );
+                    }
+                    grammar.inline(&name, &element, &mut current);
+                },
+                Event::Empty(element) if ignored == 0 => {
+                    let name = local_name(&element);
+
+                    if name == grammar.link.0 {
+                        if let Some(target) =
+                            attribute(&element, grammar.link.1)
+                        {
+                            result.links.push((target, None));
+                        }
+                    }
+                    if grammar.paragraphs.contains(&name.as_str()) {
+                        current.push('\n');
+                    }
+                    grammar.inline(&name, &element, &mut current);
+                },
+                Event::End(element) => {
+                    if ignored > 0 {
+                        ignored -= 1;
+                        continue;
+                    }
+                    let name =
+                        String::from_utf8_lossy(element.local_name().as_ref())
+                            .into_owned();
+
+                    if grammar.text.contains(&name.as_str()) {
+                        in_text -= 1;
+                    }
+                    if grammar.paragraphs.contains(&name.as_str()) {
+                        current.push('\n');
+                    }
+                    if name == grammar.link.0 {
+                        if let Some(Some((target, start))) = open_links.pop() {
+                            let text = current
+                                .get(start..)
+                                .map(|text| {
+                                    text.split_whitespace()
+                                        .collect::<Vec<_>>()
+                                        .join(" ")
+                                })
+                                .filter(|text| !text.is_empty());
+                            result.links.push((target, text));
+                        }
+                    }
+                    if grammar.section == Some(name.as_str()) {
+                        result.sections.push(std::mem::take(&mut current));
+                    }
+                },
+                Event::Text(text) if ignored == 0 && in_text > 0 => {
+                    if let Ok(text) = text.unescape() {
+                        current.push_str(&text);
+                    }
+                },
+                Event::CData(text) if ignored == 0 && in_text > 0 => {
+                    current.push_str(&String::from_utf8_lossy(&text));
+                },
+                Event::Eof => break,
+                _ => {},
+            }
+        }
+        if grammar.section.is_none() || !current.trim().is_empty() {
+            result.sections.push(current);
+        }
+
+        result.sections = result
+            .sections
+            .iter()
+            .map(|section| {
+                section
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect();
+        result
+    }
+
+    /// HTTP(S) hyperlinks. With `relationships`, targets are identifiers of
+    /// relationships.
+    fn links(
+        &self,
+        url: &Url,
+        relationships: Option<&HashMap<String, String>>,
+    ) -> Vec<Link> {
+        self.links
+            .iter()
+            .filter_map(|(target, text)| {
+                let target = match relationships {
+                    Some(relationships) => relationships.get(target)?,
+                    None => target,
+                };
+                link(url, target, text.clone())
+            })
+            .collect()
+    }
+}
+
+impl Grammar {
+    /// Text standing for an element, such as a tab.
+    fn inline(&self, name: &str, element: &BytesStart, text: &mut String) {
+        if name == self.tab {
+            text.push('\t');
+        } else if self.line_breaks.contains(&name) {
+            text.push('\n');
+        } else if self.space == Some(name) {
+            let count = attribute(element, "text:c")
+                .and_then(|count| count.parse().ok())
+                .unwrap_or(1);
+            text.push_str(&" ".repeat(count.min(MAX_SPACES)));
+        }
+    }
+}
+
+/// Relationship of an Office Open XML part.
+#[derive(Debug)]
+struct Relationship {
+    target: String,
+    external: bool,
+    hyperlink: bool,
+}
+
+/// Relationships of a part, by identifier.
+fn relationships(
+    archive: &mut Archive,
+    name: &str,
+) -> HashMap<String, Relationship> {
+    let Some(xml) = part(archive, name) else {
+        return HashMap::new();
+    };
+    let mut reader = Reader::from_str(&xml);
+    let mut relationships = HashMap::new();
+
+    while let Ok(event) = reader.read_event() {
+        match event {
+            Event::Start(element) | Event::Empty(element)
+                if element.local_name().as_ref() == b"Relationship" =>
+            {
+                let (Some(id), Some(target)) =
+                    (attribute(&element, "Id"), attribute(&element, "Target"))
+                else {
+                    continue;
+                };
+
+                relationships.insert(
+                    id,
+                    Relationship {
+                        target,
+                        external: attribute(&element, "TargetMode").as_deref()
+                            == Some("External"),
+                        hyperlink: attribute(&element, "Type")
+                            .is_some_and(|kind| kind.ends_with("/hyperlink")),
+                    },
+                );
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    relationships
+}
+
+/// Targets of the external hyperlinks of a part, by identifier.
+fn hyperlinks(archive: &mut Archive, name: &str) -> HashMap<String, String> {
+    relationships(archive, name)
+        .into_iter()
+        .filter(|(_, relationship)| {
+            relationship.hyperlink && relationship.external
+        })
+        .map(|(id, relationship)| (id, relationship.target))
+        .collect()
+}
+
+/// Properties of an Office Open XML document.
+fn ooxml_properties(archive: &mut Archive) -> Properties {
+    part(archive, "docProps/core.xml")
+        .map(|core| properties(&core))
+        .unwrap_or_default()
+}
+
+/// Reads Dublin Core properties, from Office Open XML `docProps/core.xml`
+/// or OpenDocument `meta.xml`.
+fn properties(xml: &str) -> Properties {
+    let mut reader = Reader::from_str(xml);
+    let mut values: HashMap<String, Vec<String>> = HashMap::new();
+    let mut current = None;
+
+    while let Ok(event) = reader.read_event() {
+        match event {
+            Event::Start(element) => current = Some(local_name(&element)),
+            Event::End(_) => current = None,
+            Event::Text(text) => {
+                let (Some(name), Ok(text)) = (&current, text.unescape()) else {
+                    continue;
+                };
+                let text = text.trim();
+                if !text.is_empty() {
+                    values
+                        .entry(name.clone())
+                        .or_default()
+                        .push(text.to_owned());
+                }
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    let first = |names: &[&str]| {
+        names
+            .iter()
+            .find_map(|name| {
+                values.get(*name).and_then(|values| values.first())
+            })
+            .cloned()
+    };
+    let keywords = values
+        .get("keyword")
+        .into_iter()
+        .flatten()
+        .chain(values.get("keywords").into_iter().flatten())
+        .flat_map(|keywords| keywords.split([',', ';']))
+        .map(str::trim)
+        .filter(|keyword| !keyword.is_empty())
+        .map(str::to_owned)
+        .collect();
+
+    Properties {
+        title: first(&["title"]),
+        subject: first(&["subject"]),
+        // OpenDocument `creator` is the last author.
+        author: first(&["initial-creator", "creator"]),
+        keywords,
+        description: first(&["description"]),
+        created_at: first(&["created", "creation-date"])
+            .and_then(|date| date::parse_w3c(&date)),
+        modified_at: first(&["modified", "date"])
+            .and_then(|date| date::parse_w3c(&date)),
+    }
+}
+
+/// Values of an attribute of every element with a local name.
+fn attributes(xml: &str, element: &str, name: &str) -> Vec<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut values = Vec::new();
+
+    while let Ok(event) = reader.read_event() {
+        match event {
+            Event::Start(start) | Event::Empty(start)
+                if local_name(&start) == element =>
+            {
+                values.extend(attribute(&start, name));
+            },
+            Event::Eof => break,
+            _ => {},
+        }
+    }
+
+    values
+}
+
+fn local_name(element: &BytesStart) -> String {
+    String::from_utf8_lossy(element.local_name().as_ref()).into_owned()
+}
+
+/// Unescaped value of an attribute, by qualified name.
+fn attribute(element: &BytesStart, name: &str) -> Option<String> {
+    element
+        .try_get_attribute(name)
+        .ok()
+        .flatten()
+        .and_then(|attribute| attribute.unescape_value().ok())
+        .map(|value| value.into_owned())
+}
+
+/// HTTP(S) link to a target, resolved against `url`. Links within the
+/// document are ignored.
+fn link(url: &Url, target: &str, text: Option<String>) -> Option<Link> {
+    let target = target.trim();
+    if target.starts_with('#') {
+        return None;
+    }
+    let target = url.join(target).ok()?;
+
+    matches!(target.scheme(), "http" | "https").then_some(Link {
+        url: target,
+        kind: LinkKind::Anchor,
+        text,
+        rel: Vec::new(),
+        title: None,
+        caption: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn extract(fixture: &[u8]) -> Office {
+        let url = Url::parse("https://cdn.gravitalia.com/files/").unwrap();
+        extract_office(fixture, &url).unwrap()
+    }
+
+    fn links(office: &Office) -> Vec<(&str, Option<&str>)> {
+        office
+            .links
+            .iter()
+            .map(|link| (link.url.as_str(), link.text.as_deref()))
+            .collect()
+    }
+
+    #[test]
+    fn docx_extraction() {
+        let office = extract(include_bytes!("../../fixtures/report.docx"));
+
+        assert_eq!(office.format, OfficeFormat::Docx);
+        assert_eq!(
+            office.text,
+            "Rapport annuel\nLa vie seule... c'est dur.\nAvant\taprès\nfin\n\
+             Voir notre site\nHaut de page"
+        );
+        assert!(office.slides.is_empty());
+        assert_eq!(
+            links(&office),
+            vec![("https://www.gravitalia.com/", Some("notre site"))]
+        );
+
+        let properties = office.properties;
+        assert_eq!(properties.title.as_deref(), Some("Rapport annuel"));
+        assert_eq!(properties.subject.as_deref(), Some("Polymath"));
+        assert_eq!(properties.author.as_deref(), Some("Gravitalia"));
+        assert_eq!(properties.keywords, vec!["crawler", "office"]);
+        assert_eq!(
+            properties.description.as_deref(),
+            Some("Fixture for the office extractor.")
+        );
+        assert_eq!(
+            properties.created_at,
+            date::parse_w3c("2024-01-31T12:00:00Z")
+        );
+        assert_eq!(
+            properties.modified_at,
+            date::parse_w3c("2024-02-01T08:30:00Z")
+        );
+    }
+
+    #[test]
+    fn pptx_extraction() {
+        let office = extract(include_bytes!("../../fixtures/slides.pptx"));
+
+        assert_eq!(office.format, OfficeFormat::Pptx);
+        // Slides follow the presentation order, not their file names.
+        assert_eq!(
+            office.slides,
+            vec![
+                "Polymath\nUn robot qui lit le Web",
+                "Documentation",
+                "Merci"
+            ]
+        );
+        assert_eq!(office.text, office.slides.join("\n\n"));
+        assert_eq!(
+            links(&office),
+            vec![("https://docs.gravitalia.com/polymath", None)]
+        );
+        assert_eq!(office.properties.title.as_deref(), Some("Présentation"));
+    }
+
+    #[test]
+    fn odt_extraction() {
+        let office = extract(include_bytes!("../../fixtures/notes.odt"));
+
+        assert_eq!(office.format, OfficeFormat::Odt);
+        assert_eq!(
+            office.text,
+            "Notes de réunion\nPremier point\timportant\nsuite\n\
+             Lire le blog et le haut.\nÉlément de liste"
+        );
+        assert_eq!(
+            links(&office),
+            vec![("https://www.gravitalia.com/blog", Some("le blog"))]
+        );
+
+        let properties = office.properties;
+        assert_eq!(properties.title.as_deref(), Some("Notes"));
+        assert_eq!(properties.author.as_deref(), Some("Gravitalia"));
+        assert_eq!(properties.keywords, vec!["crawler", "office"]);
+        assert_eq!(
+            properties.created_at,
+            date::parse_w3c("2024-01-31T12:00:00")
+        );
+        assert_eq!(
+            properties.modified_at,
+            date::parse_w3c("2024-02-01T08:30:00")
+        );
+    }
+
+    #[test]
+    fn odp_extraction() {
+        let office = extract(include_bytes!("../../fixtures/slides.odp"));
+
+        assert_eq!(office.format, OfficeFormat::Odp);
+        // Speaker notes are left out.
+        assert_eq!(
+            office.slides,
+            vec!["Polymath\nUn robot qui lit le Web", "Documentation"]
+        );
+        assert_eq!(
+            links(&office),
+            vec![(
+                "https://docs.gravitalia.com/polymath",
+                Some("Documentation")
+            )]
+        );
+    }
+
+    /// Stores parts in a zip archive.
+    fn archive(parts: &[(&str, &str)]) -> Vec<u8> {
+        let mut body = Vec::new();
+        let mut zip = zip::ZipWriter::new(Cursor::new(&mut body));
+        for (name, content) in parts {
+            zip.start_file(*name, zip::write::SimpleFileOptions::default())
+                .unwrap();
+            std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+        }
+        zip.finish().unwrap();
+
+        body
+    }
+
+    #[test]
+    fn odf_spaces_are_bounded() {
+        let body = archive(&[
+            ("mimetype", "application/vnd.oasis.opendocument.text"),
+            (
+                "content.xml",
+                r#"<office:document-content><office:body><office:text>
+                <text:p>a<text:s text:c="3"/>b<text:s
+                text:c="100000000000000"/>c</text:p>
+                </office:text></office:body></office:document-content>"#,
+            ),
+        ]);
+        let office = extract(&body);
+
+        assert!(office.text.starts_with("a   b "));
+        assert_eq!(office.text.len(), "a   bc".len() + MAX_SPACES);
+    }
+
+    #[test]
+    fn archive_budget() {
+        let body = archive(&[("part.xml", "0123456789")]);
+        let mut archive = Archive {
+            zip: ZipArchive::new(Cursor::new(&body[..])).unwrap(),
+            budget: 25,
+        };
+
+        assert_eq!(part(&mut archive, "part.xml").unwrap(), "0123456789");
+        assert_eq!(part(&mut archive, "part.xml").unwrap(), "0123456789");
+        assert_eq!(part(&mut archive, "part.xml").unwrap(), "01234");
+        assert_eq!(part(&mut archive, "part.xml"), None);
+    }
+
+    #[test]
+    fn legacy_extraction() {
+        let office = extract(include_bytes!("../../fixtures/legacy.doc"));
+
+        assert_eq!(office.format, OfficeFormat::Doc);
+        assert_eq!(
+            office.text,
+            "Rapport annuel\nLa vie seule... c'est dur.\nVoir\nnotre site"
+        );
+        assert_eq!(links(&office), vec![("https://www.gravitalia.com/", None)]);
+
+        let office = extract(include_bytes!("../../fixtures/legacy.ppt"));
+
+        assert_eq!(office.format, OfficeFormat::Ppt);
+        assert_eq!(
+            office.text,
+            "Polymath\nUn robot qui lit le Web\nTrès bien écrit"
+        );
+    }
+
+    #[test]
+    fn invalid_office() {
+        let url = Url::parse("https://gravitalia.com/a.docx").unwrap();
+
+        for body in [&b"PK\x03\x04 not a zip"[..], b"plain text"] {
+            assert!(matches!(
+                extract_office(body, &url).unwrap_err().error_type,
+                Scraper(ScraperError::InvalidDocument)
+            ));
+        }
+    }
+
+    #[test]
+    fn office_detection() {
+        assert!(is_office(Some("application/msword"), None));
+        assert!(is_office(Some("application/octet-stream"), Some("pptx")));
+        assert!(is_office(None, Some("odt")));
+        assert!(!is_office(Some("application/pdf"), Some("docx")));
+        assert!(!is_office(None, Some("zip")));
+    }
+}
@@ -0,0 +1,226 @@
+//! PDF text and metadata extraction.
+
+use std::time::SystemTime;
+
+use lopdf::{Dictionary, Document, Object};
+use polymath_error::{Error, ErrorType::Scraper, ScraperError};
+use url::Url;
+
+use super::date;
+use super::link::{Link, LinkKind};
+
+/// Text and metadata of a PDF document.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct Pdf {
+    /// Text of the pages, one page per line block.
+    pub text: String,
+    /// `Title` of the document information dictionary.
+    pub title: Option<String>,
+    /// `Author` of the document information dictionary.
+    pub author: Option<String>,
+    /// `CreationDate` of the document information dictionary.
+    pub created_at: Option<SystemTime>,
+    /// Number of pages.
+    pub pages: usize,
+    /// HTTP(S) links of the link annotations of the pages.
+    pub links: Vec<Link>,
+}
+
+/// Extracts the text, metadata and links of a PDF document.
+///
+/// Relative links are resolved against `url`. Pages whose text cannot be
+/// decoded are left out of [text](Pdf::text).
+pub fn extract_pdf(body: &[u8], url: &Url) -> Result<Pdf, Error> {
+    let mut document = Document::load_mem(body).map_err(|e| {
+        Error::new(
+            Scraper(ScraperError::InvalidDocument),
+            Some(Box::new(e)),
+            Some(format!("while reading PDF {}", url)),
+        )
+    })?;
+    if document.is_encrypted() {
+        // Most encrypted documents are only protected against edition,
+        // with an empty user password.
+        let _ = document.decrypt("");
+    }
+
+    let pages = document.get_pages();
+    let text = pages
+        .keys()
+        .filter_map(|number| document.extract_text(&[*number]).ok())
+        .map(|text| text.trim().to_owned())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+
+    let info = document
+        .trailer
+        .get(b"Info")
+        .and_then(|info| document.dereference(info))
+        .and_then(|(_, info)| info.as_dict())
+        .ok();
+    let field = |key: &[u8]| {
+        info.and_then(|info| info.get_deref(key, &document).ok())
+            .and_then(|value| lopdf::decode_text_string(value).ok())
+            .map(|value| value.trim().to_owned())
+            .filter(|value| !value.is_empty())
+    };
+
+    let links = pages
+        .values()
+        .filter_map(|page| document.get_page_annotations(*page).ok())
+        .flatten()
+        .filter_map(|annotation| link_of(&document, annotation, url))
+        .collect();
+
+    Ok(Pdf {
+        text,
+        title: field(b"Title"),
+        author: field(b"Author"),
+        created_at: field(b"CreationDate").as_deref().and_then(parse_date),
+        pages: pages.len(),
+        links,
+    })
+}
+
+/// Target of a link annotation opening a URI.
+fn link_of(
+    document: &Document,
+    annotation: &Dictionary,
+    url: &Url,
+) -> Option<Link> {
+    let subtype = annotation.get(b"Subtype").and_then(Object::as_name).ok()?;
+    if subtype != b"Link" {
+        return None;
+    }
+
+    let action = annotation.get_deref(b"A", document).ok()?.as_dict().ok()?;
+    let uri = action.get_deref(b"URI", document).ok()?.as_str().ok()?;
+    let uri = url.join(String::from_utf8_lossy(uri).trim()).ok()?;
+
+    matches!(uri.scheme(), "http" | "https").then(|| Link {
+        url: uri,
+        kind: LinkKind::Anchor,
+        text: None,
+        rel: Vec::new(),
+        title: None,
+        caption: None,
+    })
+}
+
+/// Parses a [PDF date](https://opensource.adobe.com/dc-acrobat-sdk-docs/pdfstandards/PDF32000_2008.pdf#G6.1961038),
+/// such as `D:20240131120000+01'00'`. Every field after the year is
+/// optional.
+fn parse_date(date: &str) -> Option<SystemTime> {
+    let date = date.strip_prefix("D:").unwrap_or(date);
+    let digits = date.bytes().take_while(u8::is_ascii_digit).count();
+    let (fields, offset) = date.split_at(digits);
+    if digits < 4 {
+        return None;
+    }
+
+    let field = |start: usize, len: usize, default: i64| {
+        fields
+            .get(start..start + len)
+            .map_or(Some(default), |field| field.parse().ok())
+    };
+    let year = field(0, 4, 0)?;
+    let month = field(4, 2, 1)?;
+    let day = field(6, 2, 1)?;
+    let seconds =
+        field(8, 2, 0)? * 3600 + field(10, 2, 0)? * 60 + field(12, 2, 0)?;
+
+    // Offset from UTC, such as `+01'00'`, `-05'30` or `Z`.
+    let mut offset_parts = offset
+        .get(1..)
+        .unwrap_or_default()
+        .split('\'')
+        .map(|part| part.parse::<i64>().unwrap_or_default());
+    let offset_seconds = offset_parts
+        .next()
+        .unwrap_or_default()
+        .checked_mul(3600)?
+        .checked_add(
+            offset_parts.next().unwrap_or_default().checked_mul(60)?,
+        )?;
+    let offset_seconds = match offset.chars().next() {
+        Some('+') => offset_seconds,
+        Some('-') => -offset_seconds,
+        _ => 0,
+    };
+
+    date::timestamp(year, month, day, seconds, offset_seconds)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn pdf_extraction() {
+        let body = testing::pdf(
+            &["Bonjour le monde", "Seconde page"],
+            &[
+                ("Title", "Rapport annuel"),
+                ("Author", "Gravitalia"),
+                ("CreationDate", "D:20240131120000+01'00'"),
+            ],
+            &[
+                "https://gravitalia.com/",
+                "/about",
+                "mailto:a@gravitalia.com",
+            ],
+        );
+        let url = Url::parse("https://cdn.gravitalia.com/rapport.pdf").unwrap();
+
+        let pdf = extract_pdf(&body, &url).unwrap();
+
+        assert_eq!(pdf.pages, 2);
+        assert!(pdf.text.contains("Bonjour le monde"), "{}", pdf.text);
+        assert!(pdf.text.contains("Seconde page"), "{}", pdf.text);
+        assert_eq!(pdf.title.as_deref(), Some("Rapport annuel"));
+        assert_eq!(pdf.author.as_deref(), Some("Gravitalia"));
+        assert_eq!(
+            pdf.created_at,
+            Some(UNIX_EPOCH + Duration::from_secs(1_706_698_800))
+        );
+        assert_eq!(
+            pdf.links
+                .iter()
+                .map(|link| link.url.as_str())
+                .collect::<Vec<_>>(),
+            vec![
+                "https://gravitalia.com/",
+                "https://cdn.gravitalia.com/about"
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_pdf() {
+        let url = Url::parse("https://gravitalia.com/a.pdf").unwrap();
+
+        assert!(matches!(
+            extract_pdf(b"%PDF-1.4 not really", &url)
+                .unwrap_err()
+                .error_type,
+            Scraper(ScraperError::InvalidDocument)
+        ));
+    }
+
+    #[test]
+    fn pdf_dates() {
+        let at = |secs| Some(UNIX_EPOCH + Duration::from_secs(secs));
+
+        assert_eq!(parse_date("D:19700101000000Z"), at(0));
+        assert_eq!(parse_date("D:2024"), at(1_704_067_200));
+        assert_eq!(parse_date("D:20240131120000-05'30'"), at(1_706_722_200));
+        assert_eq!(parse_date("20000229"), at(951_782_400));
+        assert_eq!(parse_date("D:20241301"), None);
+        assert_eq!(parse_date("hier"), None);
+        assert_eq!(parse_date("D:2024+9999999999999999'00'"), None);
+    }
+}
@@ -0,0 +1,702 @@
+//! Structured data extraction, from [JSON-LD](https://json-ld.org/),
+//! [Microdata](https://html.spec.whatwg.org/multipage/microdata.html) and
+//! [RDFa Lite](https://www.w3.org/TR/rdfa-lite/).
+//!
+//! The three syntaxes are normalized into JSON-LD-like objects: the type of
+//! an item is its `@type` member, its identifier its `@id` member, and each
+//! of its properties a member, holding an array when repeated. Types and
+//! properties of the [schema.org](https://schema.org/) vocabulary are
+//! shortened, as in `{"@context": "https://schema.org", "@type": "Product"}`.
+
+use ego_tree::NodeId;
+use polymath_error::{Error, ErrorType::Scraper, ScraperError};
+use scraper::{ElementRef, Html, Selector};
+use serde_json::{Map, Value};
+use url::Url;
+
+/// Context of the items of the schema.org vocabulary.
+const SCHEMA_ORG: &str = "https://schema.org";
+
+/// Maximum number of items nested in one another. Deeper items are left
+/// out.
+const MAX_NESTING: usize = 32;
+
+/// Prefixes of the types and properties of the schema.org vocabulary.
+const SCHEMA_ORG_PREFIXES: [&str; 3] =
+    ["https://schema.org/", "http://schema.org/", "schema:"];
+
+/// Syntax in which structured data is embedded in a page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Syntax {
+    /// `<script type="application/ld+json">` tags.
+    JsonLd,
+    /// `itemscope`, `itemtype` and `itemprop` attributes.
+    Microdata,
+    /// `vocab`, `typeof` and `property` attributes.
+    Rdfa,
+}
+
+/// Item described by the structured data of a page, such as a product or
+/// an event.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StructuredData {
+    /// Syntax of the item.
+    pub syntax: Syntax,
+    /// Item, as a JSON-LD-like object.
+    pub value: Value,
+}
+
+impl StructuredData {
+    /// Types of the item, such as `Product` for a schema.org product.
+    pub fn types(&self) -> Vec<&str> {
+        match self.value.get("@type") {
+            Some(Value::String(kind)) => vec![kind],
+            Some(Value::Array(kinds)) => {
+                kinds.iter().filter_map(Value::as_str).collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Value of a property of the item.
+    pub fn get(&self, property: &str) -> Option<&Value> {
+        self.value.get(property)
+    }
+}
+
+/// Extracts the JSON-LD, Microdata and RDFa items of an HTML page, in this
+/// order. Invalid JSON-LD scripts are ignored.
+///
+/// Relative URLs are resolved against `url`.
+pub fn extract_structured_data(
+    body: &str,
+    url: &Url,
+) -> Result<Vec<StructuredData>, Error> {
+    structured_data(&Html::parse_document(body), url)
+}
+
+/// Extracts the items of a parsed HTML page, see [extract_structured_data].
+pub(crate) fn structured_data(
+    document: &Html,
+    url: &Url,
+) -> Result<Vec<StructuredData>, Error> {
+    let mut items = Vec::new();
+
+    for script in document.select(&selector("script[type]")?) {
+        let kind = script.value().attr("type").unwrap_or_default();
+        if kind.trim().eq_ignore_ascii_case("application/ld+json") {
+            items.extend(json_ld(&script.text().collect::<String>()).map(
+                |value| StructuredData {
+                    syntax: Syntax::JsonLd,
+                    value,
+                },
+            ));
+        }
+    }
+
+    let microdata = Microdata { document, url };
+    for item in document.select(&selector("[itemscope]")?) {
+        if item.value().attr("itemprop").is_none() {
+            items.push(StructuredData {
+                syntax: Syntax::Microdata,
+                value: microdata.item(item, &mut Vec::new()),
+            });
+        }
+    }
+
+    for item in document.select(&selector("[typeof]")?) {
+        if item.value().attr("property").is_none() {
+            items.push(StructuredData {
+                syntax: Syntax::Rdfa,
+                value: rdfa_item(item, url, 0),
+            });
+        }
+    }
+
+    Ok(items)
+}
+
+fn selector(selectors: &str) -> Result<Selector, Error> {
+    Selector::parse(selectors).map_err(|_| {
+        Error::new(
+            Scraper(ScraperError::Selector),
+            None,
+            Some("while getting structured data".to_owned()),
+        )
+    })
+}
+
+/// Nodes of a JSON-LD script. Arrays and `@graph` members are flattened,
+/// their nodes inheriting the `@context` of the graph.
+fn json_ld(script: &str) -> impl Iterator<Item = Value> {
+    let script = script.trim();
+    let script = script.strip_prefix("<!--").unwrap_or(script);
+    let script = script.strip_suffix("-->").unwrap_or(script);
+
+    let nodes = match serde_json::from_str(script) {
+        Ok(Value::Array(nodes)) => nodes,
+        Ok(node) => vec![node],
+        Err(_) => Vec::new(),
+    };
+
+    nodes.into_iter().flat_map(|node| {
+        let Value::Object(mut node) = node else {
+            return Vec::new();
+        };
+        normalize_context(&mut node);
+
+        let graph = match node.remove("@graph") {
+            Some(Value::Array(graph)) => graph,
+            Some(graph) => vec![graph],
+            None => return vec![Value::Object(node)],
+        };
+        graph
+            .into_iter()
+            .filter_map(|child| {
+                let Value::Object(mut child) = child else {
+                    return None;
+                };
+                if let Some(context) = node.get("@context") {
+                    child.entry("@context").or_insert_with(|| context.clone());
+                }
+                normalize_context(&mut child);
+
+                Some(Value::Object(child))
+            })
+            .collect()
+    })
+}
+
+/// Spells the schema.org `@context` of a JSON-LD node the same way as the
+/// one of the other syntaxes.
+fn normalize_context(node: &mut Map<String, Value>) {
+    if let Some(Value::String(context)) = node.get_mut("@context") {
+        let bare = context.trim_end_matches('/');
+        if bare == SCHEMA_ORG || bare == "http://schema.org" {
+            *context = SCHEMA_ORG.to_owned();
+        }
+    }
+}
+
+/// Name of a type or property, without the schema.org prefix. `true` if it
+/// had one.
+fn shorten(name: &str) -> (&str, bool) {
+    SCHEMA_ORG_PREFIXES
+        .iter()
+        .find_map(|prefix| name.strip_prefix(prefix))
+        .map_or((name, false), |name| (name, true))
+}
+
+/// Creates an item of some types and identifier.
+fn new_item<'a>(
+    types: impl Iterator<Item = &'a str>,
+    id: Option<Url>,
+) -> Map<String, Value> {
+    let mut item = Map::new();
+    let mut schema_org = false;
+    let mut types = types
+        .map(|kind| {
+            let (kind, is_schema_org) = shorten(kind);
+            schema_org |= is_schema_org;
+            Value::String(kind.to_owned())
+        })
+        .collect::<Vec<_>>();
+
+    if schema_org {
+        item.insert("@context".to_owned(), SCHEMA_ORG.into());
+    }
+    match types.len() {
+        0 => {},
+        1 => {
+            item.insert("@type".to_owned(), types.remove(0));
+        },
+        _ => {
+            item.insert("@type".to_owned(), Value::Array(types));
+        },
+    }
+    if let Some(id) = id {
+        item.insert("@id".to_owned(), id.as_str().into());
+    }
+
+    item
+}
+
+/// Adds a value to a property of an item, which becomes an array if it
+/// already had one.
+fn insert(item: &mut Map<String, Value>, property: &str, value: Value) {
+    let property = shorten(property).0;
+
+    match item.get_mut(property) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => {
+            *existing = Value::Array(vec![existing.take(), value])
+        },
+        None => {
+            item.insert(property.to_owned(), value);
+        },
+    }
+}
+
+/// Absolute form of a URL attribute, or the attribute itself if it cannot be
+/// resolved.
+fn resolve(url: &Url, value: &str) -> String {
+    url.join(value.trim())
+        .map_or_else(|_| value.trim().to_owned(), String::from)
+}
+
+/// Child elements of an element, last first, to be pushed on a stack.
+fn reversed_children(element: ElementRef) -> impl Iterator<Item = ElementRef> {
+    element.children().rev().filter_map(ElementRef::wrap)
+}
+
+/// Whitespace-normalized text of an element.
+fn text_of(element: ElementRef) -> String {
+    element
+        .text()
+        .flat_map(str::split_whitespace)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Reader of the Microdata items of a page.
+struct Microdata<'a> {
+    document: &'a Html,
+    url: &'a Url,
+}
+
+impl Microdata<'_> {
+    /// Item of an element with an `itemscope` attribute. `parents` are the
+    /// items being read, which `itemref` attributes cannot loop back to.
+    ///
+    /// Elements are walked with an explicit stack, pages nesting them deeper
+    /// than the call stack allows. Only nested items recurse, up to
+    /// [MAX_NESTING].
+    fn item(&self, element: ElementRef, parents: &mut Vec<NodeId>) -> Value {
+        let value = element.value();
+        let mut item = new_item(
+            value
+                .attr("itemtype")
+                .unwrap_or_default()
+                .split_whitespace(),
+            value
+                .attr("itemid")
+                .and_then(|id| self.url.join(id.trim()).ok()),
+        );
+
+        // Each referenced element is read once, after the children.
+        let mut ids: Vec<&str> = Vec::new();
+        for id in value.attr("itemref").unwrap_or_default().split_whitespace() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+        let mut stack: Vec<ElementRef> = ids
+            .into_iter()
+            .rev()
+            .filter_map(|id| {
+                self.document
+                    .root_element()
+                    .descendent_elements()
+                    .find(|element| element.value().id() == Some(id))
+            })
+            .collect();
+        stack.extend(reversed_children(element));
+
+        parents.push(element.id());
+        while let Some(element) = stack.pop() {
+            if self.property(element, &mut item, parents) {
+                stack.extend(reversed_children(element));
+            }
+        }
+        parents.pop();
+
+        Value::Object(item)
+    }
+
+    /// Reads the property of an element, if any. `true` if its children
+    /// belong to the same item.
+    fn property(
+        &self,
+        element: ElementRef,
+        item: &mut Map<String, Value>,
+        parents: &mut Vec<NodeId>,
+    ) -> bool {
+        let value = element.value();
+        let is_item = value.attr("itemscope").is_some();
+
+        if let Some(names) = value.attr("itemprop") {
+            let property = if !is_item {
+                Some(Value::String(self.value(element)))
+            } else if parents.contains(&element.id()) ||
+                parents.len() >= MAX_NESTING
+            {
+                None
+            } else {
+                Some(self.item(element, parents))
+            };
+
+            for name in names.split_whitespace() {
+                if let Some(property) = &property {
+                    insert(item, name, property.clone());
+                }
+            }
+        }
+
+        !is_item
+    }
+
+    /// [Value](https://html.spec.whatwg.org/multipage/microdata.html#values)
+    /// of a property which is not an item.
+    fn value(&self, element: ElementRef) -> String {
+        let value = element.value();
+        let url_of = |attribute| {
+            value
+                .attr(attribute)
+                .map(|target| resolve(self.url, target))
+                .unwrap_or_default()
+        };
+
+        match value.name() {
+            "meta" => value.attr("content").unwrap_or_default().to_owned(),
+            "audio" | "embed" | "iframe" | "img" | "source" | "track"
+            | "video" => url_of("src"),
+            "a" | "area" | "link" => url_of("href"),
+            "object" => url_of("data"),
+            "data" | "meter" => {
+                value.attr("value").unwrap_or_default().to_owned()
+            },
+            "time" => value
+                .attr("datetime")
+                .map_or_else(|| text_of(element), str::to_owned),
+            _ => text_of(element),
+        }
+    }
+}
+
+/// Vocabulary in scope of an element: the `vocab` attribute of the element
+/// or its closest ancestor.
+fn vocabulary<'a>(element: ElementRef<'a>) -> Option<&'a str> {
+    std::iter::once(element)
+        .chain(element.ancestors().filter_map(ElementRef::wrap))
+        .find_map(|element| element.value().attr("vocab"))
+}
+
+/// Full name of an RDFa type or property, in a vocabulary.
+fn expand(name: &str, vocabulary: Option<&str>) -> String {
+    match vocabulary {
+        Some(vocabulary) if !name.contains(':') => {
+            format!("{}{}", vocabulary, name)
+        },
+        _ => name.to_owned(),
+    }
+}
+
+/// Item of an element with a `typeof` attribute, nested in `depth` items.
+///
+/// Elements are walked with an explicit stack, pages nesting them deeper
+/// than the call stack allows. Only nested items recurse, up to
+/// [MAX_NESTING].
+fn rdfa_item(element: ElementRef, url: &Url, depth: usize) -> Value {
+    let value = element.value();
+    let vocabulary = vocabulary(element);
+    let types = value
+        .attr("typeof")
+        .unwrap_or_default()
+        .split_whitespace()
+        .map(|kind| expand(kind, vocabulary))
+        .collect::<Vec<_>>();
+    let mut item = new_item(
+        types.iter().map(String::as_str),
+        value
+            .attr("resource")
+            .or_else(|| value.attr("about"))
+            .and_then(|id| url.join(id.trim()).ok()),
+    );
+
+    let mut stack: Vec<ElementRef> = reversed_children(element).collect();
+    while let Some(element) = stack.pop() {
+        if rdfa_property(element, &mut item, url, depth) {
+            stack.extend(reversed_children(element));
+        }
+    }
+
+    Value::Object(item)
+}
+
+/// Reads the RDFa property of an element, if any, for an item nested in
+/// `depth` items. `true` if its children belong to the same item.
+fn rdfa_property(
+    element: ElementRef,
+    item: &mut Map<String, Value>,
+    url: &Url,
+    depth: usize,
+) -> bool {
+    let value = element.value();
+    let is_item = value.attr("typeof").is_some();
+
+    if let Some(names) = value.attr("property") {
+        let property = if is_item {
+            if depth + 1 >= MAX_NESTING {
+                return false;
+            }
+            rdfa_item(element, url, depth + 1)
+        } else if let Some(content) = value.attr("content") {
+            Value::String(content.to_owned())
+        } else if let Some(target) = ["resource", "href", "src"]
+            .into_iter()
+            .find_map(|attribute| value.attr(attribute))
+        {
+            Value::String(resolve(url, target))
+        } else if let Some(datetime) =
+            value.attr("datetime").filter(|_| value.name() == "time")
+        {
+            Value::String(datetime.to_owned())
+        } else {
+            Value::String(text_of(element))
+        };
+
+        let vocabulary = vocabulary(element);
+        for name in names.split_whitespace() {
+            insert(item, &expand(name, vocabulary), property.clone());
+        }
+    }
+
+    !is_item
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn extract(body: &str) -> Vec<StructuredData> {
+        let url = Url::parse("https://shop.gravitalia.com/p/42").unwrap();
+        extract_structured_data(body, &url).unwrap()
+    }
+
+    #[test]
+    fn json_ld_extraction() {
+        let items = extract(
+            r#"
+            <script type="application/ld+json">
+            {
+                "@context": "http://schema.org/",
+                "@type": "Recipe",
+                "name": "Crêpes",
+                "recipeIngredient": ["Farine", "Lait", "Œufs"],
+                "cookTime": "PT2M"
+            }
+            </script>
+            <script type="application/ld+json">
+            {
+                "@context": "https://schema.org",
+                "@graph": [
+                    {"@type": "Organization", "name": "Gravitalia"},
+                    {"@type": "WebSite", "url": "https://gravitalia.com/"}
+                ]
+            }
+            </script>
+            <script type="application/ld+json">{ "broken": </script>
+            <script type="application/json">{"@type": "Ignored"}</script>
+            "#,
+        );
+
+        assert_eq!(items.len(), 3);
+        assert!(items.iter().all(|item| item.syntax == Syntax::JsonLd));
+        assert_eq!(items[0].types(), vec!["Recipe"]);
+        assert_eq!(items[0].get("@context"), Some(&json!(SCHEMA_ORG)));
+        assert_eq!(
+            items[0].get("recipeIngredient"),
+            Some(&json!(["Farine", "Lait", "Œufs"]))
+        );
+        assert_eq!(
+            items[1].value,
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Organization",
+                "name": "Gravitalia"
+            })
+        );
+        assert_eq!(items[2].types(), vec!["WebSite"]);
+    }
+
+    #[test]
+    fn microdata_extraction() {
+        let items = extract(
+            r##"
+            <div itemscope itemtype="https://schema.org/Product" itemid="#product" itemref="brand">
+                <h1 itemprop="name">Lampe   de bureau</h1>
+                <img itemprop="image" src="/lampe.jpg" alt="">
+                <img itemprop="image" src="/lampe-2.jpg" alt="">
+                <div itemprop="offers" itemscope itemtype="https://schema.org/Offer">
+                    <meta itemprop="priceCurrency" content="EUR">
+                    <data itemprop="price" value="39.90">39,90 €</data>
+                    <link itemprop="availability" href="https://schema.org/InStock">
+                    <span itemprop="name">Offre de lancement</span>
+                </div>
+                <time itemprop="releaseDate" datetime="2024-01-31">31 janvier</time>
+            </div>
+            <p id="brand">Par <span itemprop="brand">Gravitalia</span></p>
+            <div itemscope><span itemprop="note">Sans type</span></div>
+            "##,
+        );
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].syntax, Syntax::Microdata);
+        assert_eq!(
+            items[0].value,
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Product",
+                "@id": "https://shop.gravitalia.com/p/42#product",
+                "name": "Lampe de bureau",
+                "image": [
+                    "https://shop.gravitalia.com/lampe.jpg",
+                    "https://shop.gravitalia.com/lampe-2.jpg"
+                ],
+                "offers": {
+                    "@context": "https://schema.org",
+                    "@type": "Offer",
+                    "priceCurrency": "EUR",
+                    "price": "39.90",
+                    "availability": "https://schema.org/InStock",
+                    "name": "Offre de lancement"
+                },
+                "releaseDate": "2024-01-31",
+                "brand": "Gravitalia"
+            })
+        );
+        assert_eq!(items[1].value, json!({"note": "Sans type"}));
+        assert!(items[1].types().is_empty());
+    }
+
+    #[test]
+    fn microdata_loop() {
+        let items = extract(
+            r#"
+            <div itemscope itemtype="https://schema.org/Person" itemref="b">
+                <span itemprop="name">Ada</span>
+            </div>
+            <div id="b">
+                <div itemprop="knows" itemscope itemref="b">
+                    <span itemprop="name">Bob</span>
+                </div>
+            </div>
+            "#,
+        );
+
+        assert_eq!(
+            items[0].value,
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Person",
+                "name": "Ada",
+                "knows": {"name": "Bob"}
+            })
+        );
+    }
+
+    #[test]
+    fn repeated_itemref() {
+        let items = extract(
+            r#"
+            <div itemscope itemref="a a a"></div>
+            <p id="a"><span itemprop="name">Ada</span></p>
+            "#,
+        );
+
+        assert_eq!(items[0].value, json!({"name": "Ada"}));
+    }
+
+    /// Depth of the nested objects of a value.
+    fn nesting(value: &Value) -> usize {
+        let mut depth = 0;
+        let mut value = value;
+        while let Some(nested) = value
+            .as_object()
+            .and_then(|item| item.values().find(|value| value.is_object()))
+        {
+            depth += 1;
+            value = nested;
+        }
+
+        depth
+    }
+
+    #[test]
+    fn deeply_nested_items() {
+        // Deeper than a 2 MiB stack allows when walked recursively.
+        let depth = 5_000;
+        let body = format!(
+            "<div itemscope>{}{}</div>\
+            <div vocab=\"https://schema.org/\" typeof=\"Thing\">{}{}</div>\
+            <div itemscope>{}<span itemprop=\"name\">Ada</span>{}</div>",
+            r#"<div itemprop="x" itemscope>"#.repeat(depth),
+            "</div>".repeat(depth),
+            r#"<div property="x" typeof="Thing">"#.repeat(depth),
+            "</div>".repeat(depth),
+            "<div>".repeat(depth),
+            "</div>".repeat(depth),
+        );
+
+        let items = std::thread::Builder::new()
+            .stack_size(2 * 1024 * 1024)
+            .spawn(move || extract(&body))
+            .unwrap()
+            .join()
+            .unwrap();
+        assert_eq!(items.len(), 3);
+        // Microdata items come first.
+        assert_eq!(nesting(&items[0].value), MAX_NESTING - 1);
+        assert_eq!(items[1].value, json!({"name": "Ada"}));
+        assert_eq!(nesting(&items[2].value), MAX_NESTING - 1);
+    }
+
+    #[test]
+    fn rdfa_extraction() {
+        let items = extract(
+            r#"
+            <div vocab="https://schema.org/" typeof="Event" resource="/concert">
+                <h2 property="name">Concert   d'hiver</h2>
+                <time property="startDate" datetime="2024-12-21T20:00">21 décembre</time>
+                <meta property="eventStatus" content="EventScheduled">
+                <div property="location" typeof="Place">
+                    <span property="name">Salle des fêtes</span>
+                    <a property="url" href="/salle">Plan d'accès</a>
+                </div>
+                <span property="dc:creator">Mairie</span>
+            </div>
+            <div vocab="http://xmlns.com/foaf/0.1/" typeof="Person">
+                <span property="name">Ada</span>
+            </div>
+            "#,
+        );
+
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].syntax, Syntax::Rdfa);
+        assert_eq!(
+            items[0].value,
+            json!({
+                "@context": "https://schema.org",
+                "@type": "Event",
+                "@id": "https://shop.gravitalia.com/concert",
+                "name": "Concert d'hiver",
+                "startDate": "2024-12-21T20:00",
+                "eventStatus": "EventScheduled",
+                "location": {
+                    "@context": "https://schema.org",
+                    "@type": "Place",
+                    "name": "Salle des fêtes",
+                    "url": "https://shop.gravitalia.com/salle"
+                },
+                "dc:creator": "Mairie"
+            })
+        );
+        assert_eq!(items[1].types(), vec!["http://xmlns.com/foaf/0.1/Person"]);
+        assert_eq!(
+            items[1].get("http://xmlns.com/foaf/0.1/name"),
+            Some(&json!("Ada"))
+        );
+    }
+}
@@ -8,26 +8,51 @@
 )]
 //! fetch and extract datas from website.
 
+mod content;
+mod encoding;
 pub mod extractor;
+pub mod normalize;
+pub mod page;
+pub mod politeness;
+mod retry;
+mod scheduler;
+pub mod seed;
+pub mod seen;
+pub mod summary;
+#[cfg(test)]
+mod testing;
+mod visitor;
 
-#[macro_use]
-extern crate lazy_static;
-
-use extractor::meta::Meta;
-use polymath_cache::lru::LRUCache;
+use normalize::Normalizer;
+pub use page::FetchedPage;
+use politeness::{Clock, Politeness, SystemClock};
 use polymath_error::CrawlerError;
+use polymath_queue::Frontier;
+pub use polymath_queue::Strategy;
 use regex_lite::Regex;
-use std::{collections::HashMap, fmt::Debug, time::Duration};
+use scheduler::Scheduler;
+pub use seed::Seed;
+use seen::Seen;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Debug;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+pub use summary::CrawlSummary;
+use summary::{Failure, SkipReason, Skipped};
+use tokio::task::JoinSet;
 use tracing::{debug, error};
-use ureq::{Agent, Request};
+use ureq::Agent;
+use url::Url;
+use visitor::{Outcome, Response, Visitor};
 
-const ALLOWED_EXT: [&str; 16] = [
+const ALLOWED_EXT: [&str; 17] = [
     "pdf", // Adobe Portable Document Format
     "ppt", "pptx", // Microsoft PowerPoint
     "doc", "docx", // Microsoft Word
-    "odp",  // OpenOffice
-    "tex",  // Tex/LaTex
-    "txt",  // Text
+    "odp", "odt", // OpenOffice
+    "tex", // Tex/LaTex
+    "txt", // Text
     "jpeg", "jpg", "png", "webp", "gif", // Images
     "mp4", "ogv", "mov", // Videos
 ];
@@ -36,8 +61,7 @@ const ALLOWED_EXT: [&str; 16] = [
 ///
 /// # Examples
 /// ```rust
-/// use polymath_crawler::{Crawler, Event};
-/// use polymath_crawler::extractor::meta::Meta;
+/// use polymath_crawler::{Crawler, Event, FetchedPage};
 ///
 /// #[derive(Debug)]
 /// struct Solr(Crawler);
@@ -61,16 +85,14 @@ const ALLOWED_EXT: [&str; 16] = [
 ///
 ///     fn after_request(
 ///         &self,
-///         _title: &str,
-///         _meta: Vec<Meta>,
-///         _html: &str,
+///         page: &FetchedPage,
 ///     ) -> Result<(), polymath_error::Error> {
 ///         // Process or analyze the HTML content here.
 ///         // You can also save result on a database.
-///        Ok(())
+///         println!("{:?} ({})", page.title, page.final_url);
+///         Ok(())
 ///     }
 /// }
-
 /// ```
 pub trait Event: Debug + Send + Sync {
     /// Called before a URL request is made.
@@ -85,41 +107,117 @@ pub trait Event: Debug + Send + Sync {
     /// subsequent events or the crawling process for URLs found on the page.
     fn after_request(
         &self,
-        title: &str,
-        meta: Vec<extractor::meta::Meta>,
-        html: &str,
+        page: &FetchedPage,
     ) -> Result<(), polymath_error::Error>;
+    /// Called before a URL request is made, once
+    /// [before_request](Event::before_request) accepted it.
+    ///
+    /// Returns the delay the website asks between two requests (such as the
+    /// robots.txt `Crawl-delay`), which then replaces the minimum delay of
+    /// the crawler [Politeness] for this host.
+    fn crawl_delay(&self, _url: &str) -> Option<Duration> {
+        None
+    }
+    /// Called for each link found on a fetched page, before it is queued.
+    ///
+    /// Returns the link to queue, which may be rewritten, or `None` to drop
+    /// it. Links marked `nofollow` or beyond the maximum depth are dropped
+    /// before this method is called.
+    fn on_link_discovered(
+        &self,
+        _page: &FetchedPage,
+        link: extractor::link::Link,
+    ) -> Option<extractor::link::Link> {
+        Some(link)
+    }
+    /// Called when a URL cannot be crawled, or when an event fails to
+    /// process its page in [after_request](Event::after_request).
+    fn on_error(&self, _url: &str, _error: &polymath_error::Error) {}
+    /// Called once before the first request of a crawl.
+    fn on_crawl_start(&self, _seeds: &[Seed]) {}
+    /// Called once the crawl is over, with what happened to each page.
+    fn on_crawl_finish(&self, _summary: &CrawlSummary) {}
+}
+
+/// Metadata carried by each URL waiting in the crawl frontier.
+#[derive(Debug, Clone)]
+pub(crate) struct Task {
+    /// Number of hops from the starting URL.
+    pub(crate) depth: usize,
+    /// Page on which the URL was found.
+    pub(crate) referrer: Option<Arc<Url>>,
+    /// Link through which the URL was found.
+    pub(crate) link: Option<Arc<extractor::link::Link>>,
+    /// Depth budget of the seed.
+    pub(crate) max_depth: Option<usize>,
+    /// Index of the seed.
+    pub(crate) seed: usize,
+    /// URLs which redirected to the URL, starting with the one requested
+    /// first.
+    pub(crate) redirects: Vec<Url>,
 }
 
 /// The [Crawler] struct encapsulates the core functionality of a web crawler.
-#[derive(Default, Debug)]
+///
+/// Pages are fetched concurrently by [fetch_async](Crawler::fetch_async),
+/// up to [concurrency](Crawler::concurrency) pages at once and
+/// [per_host](Crawler::per_host) pages per host, while [Politeness] still
+/// spaces out the requests sent to each host. [fetch](Crawler::fetch) is its
+/// blocking counterpart.
+#[derive(Debug)]
 pub struct Crawler {
     allowed_domains: Vec<String>,
-    depth: LRUCache<String, usize>,
-    events: Vec<Box<dyn Event>>,
+    clock: Arc<dyn Clock>,
+    concurrency: usize,
+    events: Vec<Arc<dyn Event>>,
     extensions: Vec<String>,
     follow_redirects: bool,
     headers: HashMap<String, String>,
+    frontier: Frontier<Task>,
+    max_body_size: u64,
     max_depth: Option<usize>,
-    _queue: Vec<String>, // Use polymath-queue later.
-    retry_after: u64,
+    normalizer: Normalizer,
+    per_host: usize,
+    politeness: Arc<Mutex<Politeness>>,
+    respect_robots_meta: bool,
+    retry_after: Duration,
     retry_count: usize,
+    seen: Arc<Mutex<Box<dyn Seen>>>,
+    skip_non_canonical: bool,
     timeout: u64,
     user_agent: String,
 }
 
+impl Default for Crawler {
+    fn default() -> Self {
+        Crawler::new()
+    }
+}
+
 impl Crawler {
     /// Create a [Crawler] to go on pages.
     pub fn new() -> Self {
         Crawler {
+            allowed_domains: Vec::new(),
+            clock: Arc::new(SystemClock),
+            concurrency: 16,
+            events: Vec::new(),
             extensions: ALLOWED_EXT.iter().map(|e| e.to_string()).collect(),
-            timeout: 10,
-            retry_after: 10,
-            retry_count: 3,
             follow_redirects: true,
+            headers: HashMap::new(),
+            frontier: Frontier::default(),
+            max_body_size: 10 * 1024 * 1024,
+            max_depth: None,
+            normalizer: Normalizer::default(),
+            per_host: 1,
+            politeness: Arc::default(),
+            respect_robots_meta: true,
+            retry_after: Duration::from_secs(10),
+            retry_count: 3,
+            seen: Arc::default(),
+            skip_non_canonical: false,
+            timeout: 10,
             user_agent: format!("polymath/{}", env!("CARGO_PKG_VERSION")),
-            depth: LRUCache::with_capacity(20),
-            ..Default::default()
         }
     }
 
@@ -136,6 +234,9 @@ impl Crawler {
 
     /// Defines the file extensions that the crawler should fetch.
     /// Files with extensions not listed here will be excluded from the crawl.
+    ///
+    /// HTML pages are always fetched. Other files are recognized by their
+    /// URL extension before the request, then by their `Content-Type`.
     pub fn allowed_extensions(mut self, extensions: Vec<String>) -> Self {
         self.extensions = extensions;
         self
@@ -143,6 +244,10 @@ impl Crawler {
 
     /// Sets whether the crawler should follow HTTP redirects.
     /// If set to false, the crawler stops when it encounters a redirect.
+    ///
+    /// Redirect targets are queued like links, at the same depth: they go
+    /// through the same scope, robots.txt and politeness checks, and are
+    /// skipped if already crawled.
     pub fn follow_redirects(mut self, follow_redirects: bool) -> Self {
         self.follow_redirects = follow_redirects;
         self
@@ -155,28 +260,118 @@ impl Crawler {
     }
 
     /// Sets a maximum depth for the crawler. The depth is the number of hops
-    /// the crawler can make from the starting URL.
+    /// the crawler can make from the starting URL: `0` only fetches the
+    /// starting URL, `1` also fetches the pages it links to, and so on.
+    ///
+    /// [Seeds](Seed::depth) can have their own depth.
     pub fn depth(mut self, depth: usize) -> Self {
         self.max_depth = Some(depth);
         self
     }
 
+    /// Sets the rules used to canonicalize URLs before they are queued.
+    /// Defaults to [Normalizer::new].
+    pub fn normalizer(mut self, normalizer: Normalizer) -> Self {
+        self.normalizer = normalizer;
+        self
+    }
+
+    /// Sets how requests to the same host are spaced out.
+    /// Defaults to no minimum delay, with adaptive backoff.
+    pub fn politeness(mut self, politeness: Politeness) -> Self {
+        self.politeness = Arc::new(Mutex::new(politeness));
+        self
+    }
+
+    /// Sets the [Clock] used to wait between requests.
+    /// Defaults to the [system clock](politeness::SystemClock).
+    pub fn clock(mut self, clock: Box<dyn Clock>) -> Self {
+        self.clock = Arc::from(clock);
+        self
+    }
+
+    /// Sets the maximum number of pages fetched at once, across all hosts.
+    /// Defaults to 16.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Sets the maximum number of pages of the same host fetched at once.
+    /// Defaults to 1: requests to a host are sent one after the other.
+    pub fn per_host(mut self, per_host: usize) -> Self {
+        self.per_host = per_host.max(1);
+        self
+    }
+
+    /// Sets the order in which discovered URLs are crawled.
+    /// Defaults to [Strategy::BreadthFirst].
+    pub fn strategy(mut self, strategy: Strategy) -> Self {
+        self.frontier = Frontier::new(strategy);
+        self
+    }
+
+    /// Sets the backend remembering which URLs were already queued.
+    /// Defaults to an exact in-memory [set](seen::MemorySet).
+    pub fn seen(mut self, seen: Box<dyn Seen>) -> Self {
+        self.seen = Arc::new(Mutex::new(seen));
+        self
+    }
+
+    /// Sets whether pages declaring another canonical URL are skipped.
+    /// Defaults to false: variants are collapsed onto their
+    /// [canonical](FetchedPage::canonical) URL. The first one crawled is
+    /// passed to the [events](Event) and reported under the canonical URL,
+    /// which is not fetched anymore. Variants of an already crawled or
+    /// queued URL are skipped with [SkipReason::NonCanonical].
+    ///
+    /// When enabled, variants are not passed to
+    /// [after_request](Event::after_request) and their links are not
+    /// followed: only their canonical URL is queued.
+    pub fn skip_non_canonical(mut self, skip: bool) -> Self {
+        self.skip_non_canonical = skip;
+        self
+    }
+
     /// Add new receivers to Crawler [Events](Event).
     pub fn register_event(mut self, event: Box<dyn Event>) -> Self {
-        self.events.push(event);
+        self.events.push(Arc::from(event));
+        self
+    }
+
+    /// Sets whether the crawler obeys the site owner: links marked
+    /// `rel="nofollow"`, `rel="ugc"` or `rel="sponsored"` and links of pages
+    /// with a `nofollow` robots meta tag are not followed, and pages with a
+    /// `noindex` robots meta tag are not passed to
+    /// [after_request](Event::after_request).
+    ///
+    /// Enabled by default. Disable it for auditing crawls.
+    pub fn respect_robots_meta(mut self, respect_robots_meta: bool) -> Self {
+        self.respect_robots_meta = respect_robots_meta;
         self
     }
 
     /// Specifies the number of retry attempts for failed requests
-    /// (e.g., due to 4XX, 5XX errors, or timeouts).
+    /// (timeouts, connection resets, 5XX errors and `429 Too Many Requests`).
+    /// Other 4XX errors are never retried.
     pub fn retry(mut self, retry_count: usize) -> Self {
         self.retry_count = retry_count;
         self
     }
 
-    /// Sets the delay between retry attempts for failed requests.
+    /// Sets the delay before the first retry of a failed request. The delay
+    /// doubles on each attempt, with a random jitter, unless the response
+    /// has a `Retry-After` header.
     pub fn retry_after(mut self, duration: Duration) -> Self {
-        self.retry_after = duration.as_secs();
+        self.retry_after = duration;
+        self
+    }
+
+    /// Sets the maximum size of a response body, in bytes. Larger responses
+    /// fail with [BodyTooLarge](polymath_error::CrawlerError::BodyTooLarge).
+    /// Defaults to 10 MiB.
+    pub fn max_body_size(mut self, max_body_size: u64) -> Self {
+        self.max_body_size = max_body_size;
         self
     }
 
@@ -194,257 +389,1751 @@ impl Crawler {
         self
     }
 
-    fn pre_process(&self, url: &str) -> Result<(), polymath_error::Error> {
-        for event in &self.events {
-            event.before_request(&url)?;
-        }
-
-        Ok(())
-    }
-
-    fn create_agent(&self, url: &str) -> (Agent, Request) {
+    fn create_agent(&self) -> Agent {
         debug!("Creating HTTP agent to perform request.");
 
-        let agent: Agent = ureq::AgentBuilder::new()
+        ureq::AgentBuilder::new()
             .timeout(Duration::from_secs(self.timeout))
-            .redirects(if self.follow_redirects { 3 } else { 0 })
+            // Redirects are followed by the visitor, to record them.
+            .redirects(0)
             .user_agent(&self.user_agent)
-            .build();
-
-        let request = agent.get(url);
-        let request = self
-            .headers
-            .iter()
-            .fold(request, |req, (key, value)| req.set(key, value));
+            .build()
+    }
 
-        (agent, request)
+    /// Snapshot of the settings needed by the visits of a crawl.
+    fn visitor(&self) -> Visitor {
+        Visitor {
+            agent: self.create_agent(),
+            allowed_domains: self.allowed_domains.clone(),
+            clock: Arc::clone(&self.clock),
+            events: self.events.clone(),
+            extensions: self.extensions.clone(),
+            follow_redirects: self.follow_redirects,
+            headers: self.headers.clone(),
+            max_body_size: self.max_body_size,
+            normalizer: self.normalizer.clone(),
+            politeness: Arc::clone(&self.politeness),
+            respect_robots_meta: self.respect_robots_meta,
+            retry_after: self.retry_after,
+            retry_count: self.retry_count,
+            seen: Arc::clone(&self.seen),
+            skip_non_canonical: self.skip_non_canonical,
+        }
     }
 
-    fn post_process(
+    /// Canonicalize a URL and push it on the frontier unless it is outside
+    /// the scope of its seed or was already seen.
+    fn enqueue(
         &mut self,
-        agent: &Agent,
-        url: String,
-        meta: Vec<Meta>,
-        body: &str,
-    ) -> Result<(), polymath_error::Error> {
-        for event in &self.events {
-            // We do not care about result here.
-            let _ = event.after_request("", meta.clone(), body);
+        url: &Url,
+        task: Task,
+        scopes: &[Option<Vec<Regex>>],
+    ) -> Result<(), SkipReason> {
+        if let Some(scope) = &scopes[task.seed] {
+            let in_scope = url
+                .host_str()
+                .is_some_and(|host| scope.iter().any(|re| re.is_match(host)));
+            if !in_scope {
+                debug!(url = %url, "URL is outside the seed scope, skipping.");
+                return Err(SkipReason::OutOfScope);
+            }
         }
 
-        for link in extractor::link::find_all_links(body) {
-            let depth = *self.depth.get(&url).unwrap_or(&0);
-            self.depth.update(&url, depth + 1);
-
-            if let Some(depth) = self.max_depth {
-                if self.depth.get(&url).unwrap_or(&0) >= &depth {
-                    break;
-                }
-            }
+        let url = self.normalizer.normalize(url);
+        let host = scheduler::host(&url);
+        // A redirect loop is followed until it has too many redirects.
+        let looping = task
+            .redirects
+            .iter()
+            .any(|from| self.normalizer.normalize(from) == url);
+        let url = String::from(url);
 
-            debug!("Found {} URL on {}", link, url);
-            self.internal_fetch(url.clone(), &agent, link)?;
+        if self.seen.lock().unwrap().insert(&url) || looping {
+            self.frontier.push_to_group(host, url, task, 0);
+            Ok(())
+        } else {
+            debug!(url, "URL already seen, skipping.");
+            Err(SkipReason::AlreadySeen)
         }
+    }
+
+    /// Record a discovered URL which will not be fetched, once.
+    fn skip(
+        &self,
+        summary: &mut CrawlSummary,
+        recorded: &mut HashSet<String>,
+        url: &Url,
+        depth: usize,
+        reason: SkipReason,
+    ) {
+        let url = String::from(self.normalizer.normalize(url));
 
-        Ok(())
+        let seen = self.seen.lock().unwrap().contains(&url);
+        if !seen && recorded.insert(url.clone()) {
+            summary.skipped.push(Skipped { url, depth, reason });
+        }
     }
 
     /// Just fetch one page and return its content.
     /// Disabling `pre_process` enables crawling of any page, regardless of options and extensions.
+    /// Otherwise, each redirect target is checked as well.
+    ///
+    /// With `post_process`, the page is extracted as during a crawl and
+    /// passed to [after_request](Event::after_request), unless it is
+    /// `noindex` or a skipped variant. Its links are not followed nor passed
+    /// to [on_link_discovered](Event::on_link_discovered). Errors of the
+    /// events are passed to [on_error](Event::on_error).
     pub fn just_fetch(
         &self,
         url: String,
         pre_process: bool,
         post_process: bool,
     ) -> Result<String, polymath_error::Error> {
+        let visitor = self.visitor();
+
         if pre_process {
-            self.pre_process(&url)?;
+            visitor.pre_process(&url)?;
+        }
 
-            if !self.allowed_domains.is_empty() && self.test_domain(&url) {
-                return Err(
-                    polymath_error::Error::new(
-                        polymath_error::ErrorType::Crawler(CrawlerError::InvalidDomain),
+        let url = parse_url(&url)?;
+        let mut redirects = Vec::new();
+        let mut current = url.clone();
+        let mut page = loop {
+            match visitor.request(&current)? {
+                Response::Page(page) => break *page,
+                Response::Unsupported { content_type, .. } => {
+                    return Err(polymath_error::Error::new(
+                        polymath_error::ErrorType::Crawler(
+                            CrawlerError::UnsupportedContentType,
+                        ),
                         None,
-                        Some(
-                            format!(
-                                "You have specified a domain limit ({:?}) and {} is not one of them.",
-                                self.allowed_domains,
-                                url
-                            )
-                        )
-                    )
-                );
+                        Some(format!("{} served as {}", current, content_type)),
+                    ));
+                },
+                Response::Redirect { location, .. } => {
+                    if redirects.len() == visitor::MAX_REDIRECTS {
+                        return Err(visitor::too_many_redirects(&url));
+                    }
+                    if pre_process {
+                        visitor.pre_process(location.as_str())?;
+                    }
+                    redirects.push(std::mem::replace(&mut current, location));
+                },
             }
+        };
+        if !redirects.is_empty() {
+            page.url = url;
+            page.redirects = redirects;
         }
+        let body = page.text().into_owned();
 
-        let (_, request) = self.create_agent(&url);
+        if post_process {
+            // A depth budget of zero keeps the links of the page unfollowed.
+            let task = Task {
+                depth: 0,
+                referrer: None,
+                link: None,
+                max_depth: Some(0),
+                seed: 0,
+                redirects: page.redirects.clone(),
+            };
+            visitor.post_process(page, task)?;
+        }
 
-        debug!("Fetch {} using the agent.", url);
-        let body = request
-            .call()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::NetworkError,
-                    ),
-                    Some(Box::new(e)),
-                    None,
-                )
-            })?
-            .into_string()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::ParseError,
-                    ),
-                    Some(Box::new(e)),
-                    None,
-                )
-            })?;
+        Ok(body)
+    }
 
-        if post_process {
-            let meta = extractor::meta::extract_meta_tags(&body)?;
+    /// Crawl a page and extract its substantifique moelle, blocking the
+    /// current thread until the crawl is over.
+    ///
+    /// See [fetch_async](Crawler::fetch_async).
+    ///
+    /// # Panics
+    /// Panics if called from an asynchronous context: use
+    /// [fetch_async](Crawler::fetch_async) there instead.
+    pub fn fetch(&mut self, url: String) -> Result<(), polymath_error::Error> {
+        block_on(self.fetch_async(url))?
+    }
 
-            for event in &self.events {
-                let _ = event.after_request("", meta.clone(), &body);
+    /// Crawl a page and extract its substantifique moelle.
+    ///
+    /// An error is returned if the starting URL cannot be crawled; failures
+    /// on discovered pages are only logged. Use [crawl](Crawler::crawl) to
+    /// start from several URLs and get the outcome of each page.
+    ///
+    /// Must be called within a [Tokio](tokio) runtime with time enabled.
+    pub async fn fetch_async(
+        &mut self,
+        url: String,
+    ) -> Result<(), polymath_error::Error> {
+        let CrawlSummary {
+            failed, skipped, ..
+        } = self.crawl([url]).await;
+
+        // The seed is the only URL at depth 0.
+        let error = failed
+            .into_iter()
+            .find(|failure| failure.depth == 0)
+            .map(|failure| failure.error)
+            .or_else(|| {
+                skipped
+                    .into_iter()
+                    .find_map(|skipped| match skipped.reason {
+                        SkipReason::Rejected(error) if skipped.depth == 0 => {
+                            Some(error)
+                        },
+                        _ => None,
+                    })
+            });
+
+        match error {
+            Some(error) => Err(error),
+            None => Ok(()),
+        }
+    }
+
+    /// Crawl from several seeds, blocking the current thread until the
+    /// crawl is over.
+    ///
+    /// See [crawl](Crawler::crawl).
+    ///
+    /// # Panics
+    /// Panics if called from an asynchronous context: use
+    /// [crawl](Crawler::crawl) there instead.
+    pub fn crawl_blocking<I>(
+        &mut self,
+        seeds: I,
+    ) -> Result<CrawlSummary, polymath_error::Error>
+    where
+        I: IntoIterator,
+        I::Item: Into<Seed>,
+    {
+        block_on(self.crawl(seeds))
+    }
+
+    /// Crawl from several seeds and return what happened to each page.
+    ///
+    /// Discovered URLs are pushed on the crawl frontier, which is drained
+    /// according to the [Strategy] of the crawler, several pages at once.
+    /// Each page keeps the depth budget and scope of the [Seed] it was
+    /// discovered from.
+    ///
+    /// Must be called within a [Tokio](tokio) runtime with time enabled.
+    pub async fn crawl<I>(&mut self, seeds: I) -> CrawlSummary
+    where
+        I: IntoIterator,
+        I::Item: Into<Seed>,
+    {
+        let seeds: Vec<Seed> = seeds.into_iter().map(Into::into).collect();
+        let scopes: Vec<Option<Vec<Regex>>> = seeds
+            .iter()
+            .map(|seed| {
+                (!seed.allowed_domains.is_empty())
+                    .then(|| compile_domains(&seed.allowed_domains))
+            })
+            .collect();
+
+        let visitor = Arc::new(self.visitor());
+        let mut scheduler = Scheduler::new(self.per_host);
+        let mut visits = JoinSet::new();
+        let mut summary = CrawlSummary::default();
+        let mut recorded = HashSet::new();
+        let start = Instant::now();
+
+        for event in &self.events {
+            event.on_crawl_start(&seeds);
+        }
+
+        for (index, seed) in seeds.iter().enumerate() {
+            let task = Task {
+                depth: 0,
+                referrer: None,
+                link: None,
+                max_depth: seed.depth.or(self.max_depth),
+                seed: index,
+                redirects: Vec::new(),
+            };
+
+            let result = match parse_url(&seed.url) {
+                Ok(url) => self.enqueue(&url, task, &scopes),
+                Err(error) => {
+                    for event in &self.events {
+                        event.on_error(&seed.url, &error);
+                    }
+                    summary.failed.push(Failure {
+                        url: seed.url.clone(),
+                        depth: 0,
+                        error,
+                    });
+                    continue;
+                },
+            };
+
+            if let Err(reason) = result {
+                summary.skipped.push(Skipped {
+                    url: seed.url.clone(),
+                    depth: 0,
+                    reason,
+                });
             }
         }
 
-        Ok(body)
+        loop {
+            while visits.len() < self.concurrency {
+                let Some((link, task)) = scheduler.next(&mut self.frontier)
+                else {
+                    break;
+                };
+
+                let visitor = Arc::clone(&visitor);
+                let depth = task.depth;
+                visits.spawn(async move {
+                    let outcome = visitor.visit(link.clone(), task).await;
+                    (link, depth, outcome)
+                });
+            }
+
+            let (link, depth, outcome) = match visits.join_next().await {
+                Some(Ok(visit)) => visit,
+                Some(Err(error)) => {
+                    std::panic::resume_unwind(error.into_panic())
+                },
+                None => break,
+            };
+            scheduler.finish(&link);
+
+            match outcome {
+                Outcome::Fetched {
+                    links,
+                    skipped,
+                    variant_of,
+                    indexed_as,
+                } => {
+                    match variant_of {
+                        Some(canonical) => summary.skipped.push(Skipped {
+                            url: link,
+                            depth,
+                            reason: SkipReason::NonCanonical(canonical.into()),
+                        }),
+                        None => summary
+                            .fetched
+                            .push(indexed_as.map(String::from).unwrap_or(link)),
+                    }
+
+                    for (url, task) in links {
+                        let depth = task.depth;
+                        match self.enqueue(&url, task, &scopes) {
+                            Ok(()) | Err(SkipReason::AlreadySeen) => {},
+                            Err(reason) => self.skip(
+                                &mut summary,
+                                &mut recorded,
+                                &url,
+                                depth,
+                                reason,
+                            ),
+                        }
+                    }
+
+                    for (url, depth, reason) in skipped {
+                        self.skip(
+                            &mut summary,
+                            &mut recorded,
+                            &url,
+                            depth,
+                            reason,
+                        );
+                    }
+                },
+                Outcome::Redirected { location, task } => {
+                    summary.skipped.push(Skipped {
+                        url: link,
+                        depth,
+                        reason: SkipReason::Redirected(location.to_string()),
+                    });
+
+                    match self.enqueue(&location, task, &scopes) {
+                        Ok(()) | Err(SkipReason::AlreadySeen) => {},
+                        Err(reason) => self.skip(
+                            &mut summary,
+                            &mut recorded,
+                            &location,
+                            depth,
+                            reason,
+                        ),
+                    }
+                },
+                Outcome::Skipped(reason) => {
+                    debug!(url = link, "Page skipped: {}", reason);
+                    summary.skipped.push(Skipped {
+                        url: link,
+                        depth,
+                        reason,
+                    });
+                },
+                Outcome::Failed(error) => {
+                    error!(url = link, "Failed to crawl page: {}", error);
+                    summary.failed.push(Failure {
+                        url: link,
+                        depth,
+                        error,
+                    });
+                },
+            }
+        }
+
+        summary.elapsed = start.elapsed();
+        for event in &self.events {
+            event.on_crawl_finish(&summary);
+        }
+
+        summary
     }
+}
 
-    /// Crawl a page and extract its substantifique moelle.
-    pub fn fetch(&mut self, url: String) -> Result<(), polymath_error::Error> {
-        self.pre_process(&url)?;
+/// Run a future to completion on a new runtime.
+fn block_on<F: Future>(future: F) -> Result<F::Output, polymath_error::Error> {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .map_err(|e| {
+            polymath_error::Error::new(
+                polymath_error::ErrorType::Unspecified,
+                Some(Box::new(e)),
+                Some("Failed to start the crawler runtime.".to_owned()),
+            )
+        })?;
 
-        if !self.allowed_domains.is_empty() && self.test_domain(&url) {
-            return Err(
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(CrawlerError::InvalidDomain),
-                    None,
-                    Some(
-                        format!(
-                            "You have specified a domain limit ({:?}) and {} is not one of them.",
-                            self.allowed_domains,
-                            url
-                        )
-                    )
-                )
-            );
+    Ok(runtime.block_on(future))
+}
+
+/// Compile domain patterns, leaving out invalid ones.
+fn compile_domains(domains: &[String]) -> Vec<Regex> {
+    domains
+        .iter()
+        .filter_map(|domain| match Regex::new(domain) {
+            Ok(regex) => Some(regex),
+            Err(_) => {
+                error!(regex = domain, "Regex is not a valid expression.");
+                None
+            },
+        })
+        .collect()
+}
+
+fn parse_url(url: &str) -> Result<Url, polymath_error::Error> {
+    Url::parse(url).map_err(|e| {
+        polymath_error::Error::new(
+            polymath_error::ErrorType::Crawler(CrawlerError::ParseError),
+            Some(Box::new(e)),
+            Some(format!("{} is not a valid URL", url)),
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use super::*;
+    use extractor::language::LanguageSource;
+    use testing::{Response, Server};
+
+    /// [Event] recording every indexed page.
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<FetchedPage>>>);
+
+    impl Event for Recorder {
+        fn before_request(
+            &self,
+            _url: &str,
+        ) -> Result<(), polymath_error::Error> {
+            Ok(())
         }
 
-        let (agent, request) = self.create_agent(&url);
+        fn after_request(
+            &self,
+            page: &FetchedPage,
+        ) -> Result<(), polymath_error::Error> {
+            self.0.lock().unwrap().push(page.clone());
+            Ok(())
+        }
+    }
 
-        self.depth.put(url.clone(), 0);
+    /// Serve a tree of pages:
+    /// ```text
+    /// /
+    /// ├── /a
+    /// │   ├── /a/1
+    /// │   │   └── /a/1/x
+    /// │   └── /a/2
+    /// └── /b
+    ///     └── /b/1
+    /// ```
+    fn link_tree() -> Server {
+        let server = Server::start();
+        server.page("/", &["/a", "/b"]);
+        server.page("/a", &["/a/1", "/a/2"]);
+        server.page("/a/1", &["/a/1/x"]);
+        server.page("/a/1/x", &[]);
+        server.page("/a/2", &[]);
+        server.page("/b", &["/b/1"]);
+        server.page("/b/1", &[]);
+        server
+    }
 
-        debug!("Fetch {} using the agent.", url);
+    #[test]
+    fn test_depth_zero() {
+        let server = link_tree();
+        Crawler::new().depth(0).fetch(server.url("/")).unwrap();
 
-        let body = request
-            .call()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::NetworkError,
-                    ),
-                    Some(Box::new(e)),
-                    None,
-                )
-            })?
-            .into_string()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::ParseError,
-                    ),
-                    Some(Box::new(e)),
-                    None,
-                )
-            })?;
-        let meta = extractor::meta::extract_meta_tags(&body)?;
-        self.post_process(&agent, url, meta, &body)?;
+        assert_eq!(server.requests(), vec!["/"]);
+    }
+
+    #[test]
+    fn test_depth_limit() {
+        let server = link_tree();
+        Crawler::new().depth(2).fetch(server.url("/")).unwrap();
 
-        Ok(())
+        assert_eq!(
+            server.requests(),
+            vec!["/", "/a", "/b", "/a/1", "/a/2", "/b/1"]
+        );
     }
 
-    fn internal_fetch(
-        &mut self,
-        from: String,
-        agent: &ureq::Agent,
-        url: String,
-    ) -> Result<(), polymath_error::Error> {
-        self.pre_process(&url)?;
+    #[test]
+    fn test_depth_limit_depth_first() {
+        let server = link_tree();
+        Crawler::new()
+            .depth(1)
+            .strategy(Strategy::DepthFirst)
+            .fetch(server.url("/"))
+            .unwrap();
 
-        if !self.allowed_domains.is_empty() && self.test_domain(&url) {
-            return Err(
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(CrawlerError::InvalidDomain),
-                    None,
-                    Some(
-                        format!(
-                            "You have specified a domain limit ({:?}) and {} is not one of them.",
-                            self.allowed_domains,
-                            url
-                        )
-                    )
-                )
+        assert_eq!(server.requests(), vec!["/", "/b", "/a"]);
+    }
+
+    #[test]
+    fn test_seen_urls_are_skipped() {
+        let server = Server::start();
+        server.page("/", &["/a", "/b", "/a"]);
+        server.page("/a", &["/", "/b"]);
+        server.page("/b", &["/a", "/"]);
+
+        Crawler::new().fetch(server.url("/")).unwrap();
+        assert_eq!(server.requests(), vec!["/", "/a", "/b"]);
+
+        // A URL seen by a previous crawl is not fetched again.
+        let mut crawler =
+            Crawler::new().seen(Box::new(seen::BloomFilter::new(100, 0.01)));
+        crawler.fetch(server.url("/")).unwrap();
+        crawler.fetch(server.url("/")).unwrap();
+        assert_eq!(server.requests(), vec!["/", "/a", "/b", "/", "/a", "/b"]);
+    }
+
+    #[test]
+    fn test_urls_are_normalized() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(&format!(
+                r#"<a href="/b?utm_source=x&a=1#top">b</a>
+                <a href="./b?a=1">b</a>
+                <a href="{}">b</a>"#,
+                server.url("/c/../b?fbclid=42&a=1")
+            ))],
+        );
+        server.page("/b?a=1", &[]);
+
+        Crawler::new().fetch(server.url("/#top")).unwrap();
+        assert_eq!(server.requests(), vec!["/", "/b?a=1"]);
+    }
+
+    fn robots_meta_site() -> Server {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(
+                r#"<a href="/a">a</a>
+                <a href="/b" rel="nofollow">b</a>
+                <a href="/c" rel="external UGC">c</a>
+                <a href="/d" rel="sponsored">d</a>"#,
+            )],
+        );
+        server.route(
+            "/a",
+            vec![Response::html(
+                r#"<meta name="robots" content="noindex">
+                <a href="/e">e</a>"#,
+            )],
+        );
+        server.route(
+            "/e",
+            vec![Response::html(
+                r#"<meta name="robots" content="nofollow">
+                <a href="/f">f</a>"#,
+            )],
+        );
+        for path in ["/b", "/c", "/d", "/f"] {
+            server.page(path, &[]);
+        }
+        server
+    }
+
+    #[test]
+    fn test_robots_meta() {
+        let server = robots_meta_site();
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/a", "/e"]);
+        // `/a` is not indexed.
+        assert_eq!(recorder.0.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_robots_meta_ignored() {
+        let server = robots_meta_site();
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .respect_robots_meta(false)
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(
+            server.requests(),
+            vec!["/", "/a", "/b", "/c", "/d", "/e", "/f"]
+        );
+        assert_eq!(recorder.0.lock().unwrap().len(), 7);
+    }
+
+    /// [Clock] recording sleeps instead of waiting.
+    #[derive(Debug, Clone)]
+    struct FakeClock {
+        now: Arc<Mutex<std::time::Instant>>,
+        sleeps: Arc<Mutex<Vec<Duration>>>,
+    }
+
+    impl FakeClock {
+        fn new() -> Self {
+            FakeClock {
+                now: Arc::new(Mutex::new(std::time::Instant::now())),
+                sleeps: Arc::new(Mutex::new(Vec::new())),
+            }
+        }
+    }
+
+    impl Clock for FakeClock {
+        fn now(&self) -> std::time::Instant {
+            *self.now.lock().unwrap()
+        }
+
+        fn sleep(&self, duration: Duration) -> politeness::Sleep {
+            *self.now.lock().unwrap() += duration;
+            self.sleeps.lock().unwrap().push(duration);
+            Box::pin(std::future::ready(()))
+        }
+    }
+
+    /// [Event] asking for a crawl delay.
+    #[derive(Debug)]
+    struct CrawlDelay(Duration);
+
+    impl Event for CrawlDelay {
+        fn before_request(
+            &self,
+            _url: &str,
+        ) -> Result<(), polymath_error::Error> {
+            Ok(())
+        }
+
+        fn after_request(
+            &self,
+            _page: &FetchedPage,
+        ) -> Result<(), polymath_error::Error> {
+            Ok(())
+        }
+
+        fn crawl_delay(&self, _url: &str) -> Option<Duration> {
+            Some(self.0)
+        }
+    }
+
+    #[test]
+    fn test_politeness() {
+        let server = Server::start();
+        server.page("/", &["/a", "/b"]);
+        server.page("/a", &[]);
+        server.route("/b", vec![Response::status(503)]);
+        let clock = FakeClock::new();
+
+        Crawler::new()
+            .politeness(Politeness::new(Duration::from_secs(2)))
+            .retry(0)
+            .clock(Box::new(clock.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/a", "/b"]);
+        assert_eq!(
+            *clock.sleeps.lock().unwrap(),
+            vec![Duration::from_secs(2), Duration::from_secs(2)]
+        );
+    }
+
+    #[test]
+    fn test_politeness_crawl_delay() {
+        let server = link_tree();
+        let clock = FakeClock::new();
+
+        Crawler::new()
+            .depth(1)
+            .politeness(Politeness::new(Duration::from_secs(2)))
+            .register_event(Box::new(CrawlDelay(Duration::from_secs(5))))
+            .clock(Box::new(clock.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(
+            *clock.sleeps.lock().unwrap(),
+            vec![Duration::from_secs(5), Duration::from_secs(5)]
+        );
+    }
+
+    #[test]
+    fn test_fetched_page() {
+        let server = Server::start();
+        server.page("/", &["/old"]);
+        server.route(
+            "/old",
+            vec![Response::status(301).header("Location", "/new")],
+        );
+        server.route(
+            "/new",
+            vec![Response::html(
+                r#"<html><head><title>Nouvelle page</title>
+                <meta name="description" content="Déplacée"></head></html>"#,
+            )
+            .header("X-Polymath", "1")],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].depth, 0);
+        assert_eq!(pages[0].referrer, None);
+        assert_eq!(pages[0].title, None);
+
+        let page = &pages[1];
+        assert_eq!(page.url.as_str(), server.url("/old"));
+        assert_eq!(page.final_url.as_str(), server.url("/new"));
+        assert_eq!(page.redirects, vec![page.url.clone()]);
+        assert!(page.is_redirected());
+        assert_eq!(page.status, 200);
+        assert_eq!(page.content_type.as_deref(), Some("text/html"));
+        assert_eq!(page.header("x-polymath"), Some("1"));
+        assert!(page.text().contains("Nouvelle page"));
+        assert_eq!(page.depth, 1);
+        assert_eq!(
+            page.referrer.as_ref().map(Url::as_str),
+            Some(server.url("/").as_str())
+        );
+        assert_eq!(page.title.as_deref(), Some("Nouvelle page"));
+        assert_eq!(page.meta[0].content.as_deref(), Some("Déplacée"));
+    }
+
+    /// [Event] refusing the URLs under `/private`, as a robots.txt would.
+    #[derive(Debug)]
+    struct Private;
+
+    impl Event for Private {
+        fn before_request(
+            &self,
+            url: &str,
+        ) -> Result<(), polymath_error::Error> {
+            match Url::parse(url) {
+                Ok(url) if url.path().starts_with("/private") => {
+                    Err(polymath_error::Error::new(
+                        polymath_error::ErrorType::Crawler(
+                            CrawlerError::DisallowedByRobots,
+                        ),
+                        None,
+                        None,
+                    ))
+                },
+                _ => Ok(()),
+            }
+        }
+
+        fn after_request(
+            &self,
+            _page: &FetchedPage,
+        ) -> Result<(), polymath_error::Error> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_redirect_targets_are_checked() {
+        let server = Server::start();
+        let outside = server.url("/").replace("127.0.0.1", "localhost");
+        server.page("/", &["/a", "/b", "/c"]);
+        for (path, location) in [
+            ("/a", server.url("/private")),
+            ("/b", outside),
+            ("/c", server.url("/")),
+        ] {
+            server.route(
+                path,
+                vec![Response::status(302).header("Location", &location)],
             );
         }
+        let recorder = Recorder::default();
+
+        let summary = Crawler::new()
+            .allowed_domains(vec![r"^127\.0\.0\.1$".to_owned()])
+            .register_event(Box::new(Private))
+            .register_event(Box::new(recorder.clone()))
+            .crawl_blocking([server.url("/")])
+            .unwrap();
+
+        // Neither the disallowed targets nor the crawled page are fetched.
+        assert_eq!(server.requests(), vec!["/", "/a", "/b", "/c"]);
+        assert_eq!(recorder.0.lock().unwrap().len(), 1);
+        assert_eq!(summary.fetched, vec![server.url("/")]);
+        let rejected = summary
+            .skipped
+            .iter()
+            .filter(|skipped| matches!(skipped.reason, SkipReason::Rejected(_)))
+            .count();
+        assert_eq!(rejected, 2);
+    }
 
-        let mut request = agent.get(&url);
+    #[test]
+    fn test_redirect_loop() {
+        let server = Server::start();
+        server.route(
+            "/loop",
+            vec![Response::status(302).header("Location", "/loop")],
+        );
 
-        for (key, value) in &self.headers {
-            request = request.clone().set(key, value);
+        let error = Crawler::new().fetch(server.url("/loop")).unwrap_err();
+        assert!(matches!(
+            error.error_type,
+            polymath_error::ErrorType::Crawler(CrawlerError::TooManyRedirects)
+        ));
+        assert_eq!(server.requests().len(), 4);
+
+        // Without following redirects, the redirect itself is the page.
+        let recorder = Recorder::default();
+        Crawler::new()
+            .follow_redirects(false)
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/loop"))
+            .unwrap();
+        assert_eq!(recorder.0.lock().unwrap()[0].status, 302);
+    }
+
+    /// [Event] recording lifecycle hooks, dropping links to `/b` and
+    /// rewriting links to `/a` into `/a/2`.
+    #[derive(Debug, Default, Clone)]
+    struct Hooks(Arc<Mutex<Vec<String>>>);
+
+    impl Event for Hooks {
+        fn before_request(
+            &self,
+            _url: &str,
+        ) -> Result<(), polymath_error::Error> {
+            Ok(())
         }
 
-        debug!("Fetch {} using the agent.", url);
-        let body = request
-            .call()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::NetworkError,
-                    ),
-                    Some(Box::new(e)),
+        fn after_request(
+            &self,
+            page: &FetchedPage,
+        ) -> Result<(), polymath_error::Error> {
+            if page.url.path() == "/a/2" {
+                return Err(polymath_error::Error::new(
+                    polymath_error::ErrorType::Unspecified,
                     None,
-                )
-            })?
-            .into_string()
-            .map_err(|e| {
-                polymath_error::Error::new(
-                    polymath_error::ErrorType::Crawler(
-                        CrawlerError::ParseError,
-                    ),
-                    Some(Box::new(e)),
                     None,
+                ));
+            }
+            Ok(())
+        }
+
+        fn on_link_discovered(
+            &self,
+            _page: &FetchedPage,
+            mut link: extractor::link::Link,
+        ) -> Option<extractor::link::Link> {
+            match link.url.path() {
+                "/b" => None,
+                "/a" => {
+                    link.url.set_path("/a/2");
+                    Some(link)
+                },
+                _ => Some(link),
+            }
+        }
+
+        fn on_error(&self, url: &str, _error: &polymath_error::Error) {
+            let path = Url::parse(url).map(|url| url.path().to_owned());
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("error {}", path.unwrap_or_default()));
+        }
+
+        fn on_crawl_start(&self, seeds: &[Seed]) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(format!("start {}", seeds.len()));
+        }
+
+        fn on_crawl_finish(&self, summary: &CrawlSummary) {
+            self.0.lock().unwrap().push(format!(
+                "finish {} {} {}",
+                summary.fetched.len(),
+                summary.failed.len(),
+                summary.skipped.len()
+            ));
+        }
+    }
+
+    #[test]
+    fn test_hooks() {
+        let server = link_tree();
+        server.route("/missing", vec![Response::status(404)]);
+        let hooks = Hooks::default();
+
+        let summary = Crawler::new()
+            .depth(1)
+            .register_event(Box::new(hooks.clone()))
+            .crawl_blocking([server.url("/"), server.url("/missing")])
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/missing", "/a/2"]);
+        assert!(matches!(summary.skipped[0].reason, SkipReason::Filtered));
+        assert_eq!(
+            *hooks.0.lock().unwrap(),
+            vec!["start 2", "error /missing", "error /a/2", "finish 2 1 1"]
+        );
+    }
+
+    #[test]
+    fn test_just_fetch() {
+        let server = link_tree();
+        server.route(
+            "/article",
+            vec![Response::html(include_str!(
+                "../fixtures/articles/blog.html"
+            ))],
+        );
+        let hooks = Hooks::default();
+        let recorder = Recorder::default();
+        let crawler = Crawler::new()
+            .register_event(Box::new(hooks.clone()))
+            .register_event(Box::new(recorder.clone()));
+
+        // Pages are extracted as during a crawl.
+        let body = crawler
+            .just_fetch(server.url("/article"), true, true)
+            .unwrap();
+        assert!(body.contains("Popular posts"));
+        let pages = recorder.0.lock().unwrap().clone();
+        assert!(pages[0].article.is_some());
+        assert!(pages[0].language.is_some());
+
+        // Errors of the events are reported, links are not followed.
+        crawler.just_fetch(server.url("/a/2"), true, true).unwrap();
+        crawler.just_fetch(server.url("/a"), true, false).unwrap();
+        assert_eq!(*hooks.0.lock().unwrap(), vec!["error /a/2"]);
+        assert_eq!(recorder.0.lock().unwrap().len(), 2);
+        assert_eq!(server.requests(), vec!["/article", "/a/2", "/a"]);
+    }
+
+    #[test]
+    fn test_crawl_summary() {
+        let server = Server::start();
+        let outside = server.url("/d").replace("127.0.0.1", "localhost");
+        server.route(
+            "/",
+            vec![Response::html(&format!(
+                r#"<a href="{}">a</a><a href="{}">b</a>
+                <a href="{}" rel="nofollow">c</a><a href="{}">d</a>"#,
+                server.url("/a"),
+                server.url("/b"),
+                server.url("/c"),
+                outside
+            ))],
+        );
+        server.page("/a", &["/a/1"]);
+        server.route("/b", vec![Response::status(500)]);
+
+        let summary = Crawler::new()
+            .retry(0)
+            .crawl_blocking(vec![
+                Seed::new(server.url("/"))
+                    .depth(1)
+                    .allowed_domains(vec![r"^127\.0\.0\.1$".to_owned()]),
+                Seed::from("not a URL"),
+                Seed::from(server.url("/")),
+            ])
+            .unwrap();
+
+        let mut fetched = summary.fetched.clone();
+        fetched.sort();
+        assert_eq!(fetched, vec![server.url("/"), server.url("/a")]);
+
+        let failed: Vec<_> = summary
+            .failed
+            .iter()
+            .map(|failure| (failure.url.as_str(), failure.depth))
+            .collect();
+        assert_eq!(failed.len(), 2);
+        assert!(failed.contains(&("not a URL", 0)));
+        assert!(failed.contains(&(server.url("/b").as_str(), 1)));
+
+        let skipped: Vec<_> = summary
+            .skipped
+            .iter()
+            .map(|skipped| {
+                (
+                    skipped.url.as_str(),
+                    skipped.depth,
+                    skipped.reason.to_string(),
                 )
-            })?;
-
-        let meta = extractor::meta::extract_meta_tags(&body)?;
-
-        self.post_process(&agent, from, meta, &body)?;
-
-        Ok(())
-    }
-
-    fn test_domain(&self, url: &str) -> bool {
-        url::Url::parse(url)
-            .map(|url| {
-                url.host_str()
-                    .map(|host| {
-                        self.allowed_domains.iter().any(|domain| {
-                            if let Ok(regex) = Regex::new(domain) {
-                                regex.is_match(host)
-                            } else {
-                                error!(
-                                    regex = domain,
-                                    "Regex is not a valid expression."
-                                );
-                                false
-                            }
-                        })
-                    })
-                    .unwrap_or(false)
             })
-            .unwrap_or(false)
+            .collect();
+        assert_eq!(skipped.len(), 4);
+        for expected in [
+            (server.url("/"), 0, SkipReason::AlreadySeen),
+            (server.url("/c"), 1, SkipReason::NoFollow),
+            (outside, 1, SkipReason::OutOfScope),
+            (server.url("/a/1"), 2, SkipReason::MaxDepth),
+        ] {
+            let expected =
+                (expected.0.as_str(), expected.1, expected.2.to_string());
+            assert!(skipped.contains(&expected), "{:?}", expected);
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_concurrency() {
+        let first = Server::start();
+        let second = Server::start();
+        let slow =
+            Response::html("<p>Lent</p>").delay(Duration::from_millis(200));
+        let mut links = Vec::new();
+        for i in 0..4 {
+            let path = format!("/{}", i);
+            first.route(&path, vec![slow.clone()]);
+            second.route(&path, vec![slow.clone()]);
+            links.push(first.url(&path));
+            links.push(second.url(&path));
+        }
+        first.route(
+            "/",
+            vec![Response::html(
+                &links
+                    .iter()
+                    .map(|link| format!(r#"<a href="{}">link</a>"#, link))
+                    .collect::<String>(),
+            )],
+        );
+
+        Crawler::new()
+            .concurrency(4)
+            .per_host(2)
+            .fetch_async(first.url("/"))
+            .await
+            .unwrap();
+
+        assert_eq!(first.requests().len(), 5);
+        assert_eq!(second.requests().len(), 4);
+        assert_eq!(first.max_concurrent(), 2);
+        assert_eq!(second.max_concurrent(), 2);
+    }
+
+    #[test]
+    fn test_retry() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![
+                Response::status(503),
+                Response::status(500),
+                Response::html("<p>Enfin !</p>"),
+            ],
+        );
+        let clock = FakeClock::new();
+
+        Crawler::new()
+            .retry(3)
+            .retry_after(Duration::from_secs(2))
+            .clock(Box::new(clock.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/", "/"]);
+        let sleeps = clock.sleeps.lock().unwrap();
+        assert_eq!(sleeps.len(), 2);
+        assert!(sleeps[0] >= Duration::from_secs(1));
+        assert!(sleeps[0] <= Duration::from_secs(2));
+        assert!(sleeps[1] >= Duration::from_secs(2));
+        assert!(sleeps[1] <= Duration::from_secs(4));
+    }
+
+    #[test]
+    fn test_retry_after_header() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![
+                Response::status(429).header("Retry-After", "7"),
+                Response::html("<p>Enfin !</p>"),
+            ],
+        );
+        let clock = FakeClock::new();
+
+        Crawler::new()
+            .clock(Box::new(clock.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/"]);
+        assert_eq!(*clock.sleeps.lock().unwrap(), vec![Duration::from_secs(7)]);
+    }
+
+    #[test]
+    fn test_retry_exhausted() {
+        let server = Server::start();
+        server.route("/", vec![Response::status(502)]);
+        server.route("/missing", vec![Response::status(404)]);
+        let clock = FakeClock::new();
+
+        let mut crawler =
+            Crawler::new().retry(2).clock(Box::new(clock.clone()));
+        assert!(crawler.fetch(server.url("/")).is_err());
+        assert_eq!(server.requests(), vec!["/", "/", "/"]);
+
+        // Client errors are not retried.
+        assert!(crawler.fetch(server.url("/missing")).is_err());
+        assert_eq!(server.requests().len(), 4);
+    }
+
+    fn error_type(result: Result<(), polymath_error::Error>) -> CrawlerError {
+        match result.unwrap_err().error_type {
+            polymath_error::ErrorType::Crawler(error) => error,
+            error_type => panic!("unexpected error: {:?}", error_type),
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        let server = Server::start();
+        server.route("/missing", vec![Response::status(404)]);
+        server.route(
+            "/loop",
+            vec![Response::status(301).header("Location", "/loop")],
+        );
+        let mut crawler = Crawler::new().follow_redirects(true).retry(0);
+
+        assert_eq!(
+            error_type(crawler.fetch(server.url("/missing"))),
+            CrawlerError::HttpStatus(404)
+        );
+        assert_eq!(
+            error_type(crawler.fetch(server.url("/loop"))),
+            CrawlerError::TooManyRedirects
+        );
+        assert_eq!(
+            error_type(crawler.fetch("http://polymath.invalid/".to_owned())),
+            CrawlerError::Dns
+        );
+
+        // Nothing listens on a port once its listener is dropped.
+        let address = std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        assert_eq!(
+            error_type(crawler.fetch(format!("http://{}/", address))),
+            CrawlerError::NetworkError
+        );
+    }
+
+    #[test]
+    fn test_permanent_errors_not_retried() {
+        let server = Server::start();
+        server.route(
+            "/ftp",
+            vec![Response::status(301).header("Location", "ftp://127.0.0.1/")],
+        );
+        let clock = FakeClock::new();
+        let mut crawler = Crawler::new()
+            .follow_redirects(true)
+            .retry(3)
+            .clock(Box::new(clock.clone()));
+
+        assert_eq!(
+            error_type(crawler.fetch(server.url("/ftp"))),
+            CrawlerError::InvalidRequest
+        );
+        // Invalid URLs are rejected before being requested.
+        assert_eq!(
+            error_type(crawler.fetch("http://[::1/".to_owned())),
+            CrawlerError::ParseError
+        );
+        assert_eq!(server.requests(), vec!["/ftp"]);
+        assert!(clock.sleeps.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_tls_error() {
+        // Close connections before the TLS handshake completes.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                drop(stream);
+            }
+        });
+
+        let result = Crawler::new()
+            .retry(0)
+            .fetch(format!("https://{}/", address));
+        assert_eq!(error_type(result), CrawlerError::Tls);
+    }
+
+    #[test]
+    fn test_timeout() {
+        // Accept connections but never respond.
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        std::thread::spawn(move || {
+            let _streams: Vec<_> = listener.incoming().collect();
+        });
+
+        let result = Crawler::new()
+            .timeout(Duration::from_secs(1))
+            .retry(0)
+            .fetch(format!("http://{}/", address));
+        assert_eq!(error_type(result), CrawlerError::Timeout);
+    }
+
+    #[test]
+    fn test_no_depth_limit() {
+        let server = link_tree();
+        Crawler::new().fetch(server.url("/")).unwrap();
+
+        assert_eq!(
+            server.requests(),
+            vec!["/", "/a", "/b", "/a/1", "/a/2", "/b/1", "/a/1/x"]
+        );
+    }
+
+    #[test]
+    fn test_encoding() {
+        let (japanese, _, _) = encoding_rs::SHIFT_JIS
+            .encode("<html><head><title>日本語</title></head></html>");
+
+        let server = Server::start();
+        server.page("/", &["/latin", "/japanese"]);
+        server.route(
+            "/latin",
+            vec![Response::bytes(
+                "text/html",
+                b"<html><head><meta charset=\"iso-8859-1\">\
+                <title>Caf\xe9 cr\xe8me</title></head></html>",
+            )],
+        );
+        server.route(
+            "/japanese",
+            vec![Response::bytes("text/html; charset=Shift_JIS", &japanese)],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages[0].encoding.as_deref(), Some("UTF-8"));
+
+        let latin = pages.iter().find(|page| page.url.path() == "/latin");
+        let latin = latin.unwrap();
+        assert_eq!(latin.encoding.as_deref(), Some("windows-1252"));
+        assert_eq!(latin.title.as_deref(), Some("Café crème"));
+
+        let japanese = pages.iter().find(|page| page.url.path() == "/japanese");
+        let japanese = japanese.unwrap();
+        assert_eq!(japanese.encoding.as_deref(), Some("Shift_JIS"));
+        assert_eq!(japanese.title.as_deref(), Some("日本語"));
+    }
+
+    #[test]
+    fn test_allowed_extensions() {
+        const PDF: &[u8] = b"%PDF-1.4\n\xe2\xe3\xcf\xd3\n%%EOF";
+
+        let server = Server::start();
+        server.page("/", &["/doc.pdf", "/image.png", "/file", "/data"]);
+        server.route("/doc.pdf", vec![Response::bytes("application/pdf", PDF)]);
+        server.route("/image.png", vec![Response::bytes("image/png", b"")]);
+        server.route(
+            "/file",
+            vec![Response::bytes("application/x-unknown", b"")],
+        );
+        server.route("/data", vec![Response::bytes("image/png", b"")]);
+        let recorder = Recorder::default();
+
+        let summary = Crawler::new()
+            .allowed_extensions(vec!["pdf".to_owned()])
+            .register_event(Box::new(recorder.clone()))
+            .crawl_blocking(vec![server.url("/")])
+            .unwrap();
+
+        // The PNG extension is known: its URL is not requested.
+        assert_eq!(server.requests(), vec!["/", "/doc.pdf", "/file", "/data"]);
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].content_type.as_deref(), Some("application/pdf"));
+        assert_eq!(pages[1].body, PDF);
+        assert_eq!(pages[1].encoding, None);
+        // The body is not a valid PDF document.
+        assert_eq!(pages[1].document, None);
+        assert_eq!(pages[1].title, None);
+
+        let mut skipped: Vec<_> = summary
+            .skipped
+            .iter()
+            .map(|skipped| (skipped.url.clone(), skipped.reason.to_string()))
+            .collect();
+        skipped.sort();
+        assert_eq!(
+            skipped,
+            vec![
+                (
+                    server.url("/data"),
+                    SkipReason::ContentType("image/png".to_owned()).to_string()
+                ),
+                (
+                    server.url("/file"),
+                    SkipReason::ContentType("application/x-unknown".to_owned())
+                        .to_string()
+                ),
+                (
+                    server.url("/image.png"),
+                    SkipReason::Extension("png".to_owned()).to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pdf() {
+        let server = Server::start();
+        server.page("/", &["/report.pdf"]);
+        server.route(
+            "/report.pdf",
+            vec![Response::bytes(
+                "application/pdf",
+                &testing::pdf(
+                    &["Annual report"],
+                    &[("Title", "Report"), ("Author", "Gravitalia")],
+                    &["/from-pdf"],
+                ),
+            )],
+        );
+        server.page("/from-pdf", &[]);
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        assert_eq!(server.requests(), vec!["/", "/report.pdf", "/from-pdf"]);
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages[1].title.as_deref(), Some("Report"));
+        let Some(extractor::Document::Pdf(pdf)) = &pages[1].document else {
+            panic!("no PDF: {:?}", pages[1].document);
+        };
+        assert_eq!(pdf.author.as_deref(), Some("Gravitalia"));
+        assert!(pdf.text.contains("Annual report"));
+        assert_eq!(pages[2].referrer, Some(pages[1].url.clone()));
+    }
+
+    #[test]
+    fn test_article() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(include_str!(
+                "../fixtures/articles/blog.html"
+            ))],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .allowed_domains(vec![r"^127\.0\.0\.1$".to_owned()])
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        let article = pages[0].article.as_ref().unwrap();
+        assert_eq!(article.headings.len(), 3);
+        assert!(!article.text.contains("Popular posts"));
+    }
+
+    #[test]
+    fn test_metadata() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(
+                r#"<html lang="fr"><head>
+                <link rel="canonical" href="/article">
+                <meta property="og:image" content="/cover.png">
+                <meta name="twitter:card" content="summary">
+                </head></html>"#,
+            )],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        let metadata = pages[0].metadata.as_ref().unwrap();
+        assert_eq!(metadata.language.as_deref(), Some("fr"));
+        assert_eq!(
+            metadata.canonical.as_ref().map(Url::as_str),
+            Some(server.url("/article").as_str())
+        );
+        assert_eq!(
+            metadata.open_graph.images[0].url.as_str(),
+            server.url("/cover.png")
+        );
+        assert_eq!(metadata.twitter.card.as_deref(), Some("summary"));
+    }
+
+    #[test]
+    fn test_canonical() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(&format!(
+                r#"<a href="{}">a</a><a href="{}">file</a>"#,
+                server.url("/a?v=2"),
+                server.url("/file.txt")
+            ))],
+        );
+        server.route(
+            "/a?v=2",
+            vec![Response::html(&format!(
+                r#"<link rel="canonical" href="/a">
+                <link rel="alternate" hreflang="fr" href="/fr/a">
+                <a href="{}">b</a>"#,
+                server.url("/b")
+            ))],
+        );
+        server.route(
+            "/file.txt",
+            vec![Response::bytes("text/plain", b"text").header(
+                "Link",
+                &format!(r#"<{}>; rel="canonical""#, server.url("/file")),
+            )],
+        );
+        server.page("/a", &[]);
+        server.page("/b", &[]);
+        server.page("/file", &[]);
+        let crawl = |skip| {
+            let recorder = Recorder::default();
+            let summary = Crawler::new()
+                .skip_non_canonical(skip)
+                .register_event(Box::new(recorder.clone()))
+                .crawl_blocking(vec![Seed::new(server.url("/")).depth(2)])
+                .unwrap();
+            let pages = recorder.0.lock().unwrap().clone();
+
+            (summary, pages)
+        };
+
+        let (summary, pages) = crawl(false);
+        let variant = pages
+            .iter()
+            .find(|page| page.url.as_str() == server.url("/a?v=2"))
+            .unwrap();
+        assert_eq!(
+            variant.canonical.as_ref().map(Url::as_str),
+            Some(server.url("/a").as_str())
+        );
+        let cluster = variant.language_cluster.as_ref().unwrap();
+        assert_eq!(
+            cluster.get("fr").map(Url::as_str),
+            Some(server.url("/fr/a").as_str())
+        );
+        let file = pages
+            .iter()
+            .find(|page| page.url.as_str() == server.url("/file.txt"))
+            .unwrap();
+        assert_eq!(
+            file.canonical.as_ref().map(Url::as_str),
+            Some(server.url("/file").as_str())
+        );
+        for path in ["/a", "/b", "/file"] {
+            assert!(summary.fetched.contains(&server.url(path)));
+        }
+
+        let (summary, pages) = crawl(true);
+        assert!(pages.iter().all(|page| page.canonical.is_none()));
+        assert!(summary.fetched.contains(&server.url("/a")));
+        assert!(!summary.fetched.contains(&server.url("/b")));
+        let skipped = summary
+            .skipped
+            .iter()
+            .find(|skipped| skipped.url == server.url("/a?v=2"))
+            .unwrap();
+        assert!(matches!(
+            &skipped.reason,
+            SkipReason::NonCanonical(canonical)
+                if *canonical == server.url("/a")
+        ));
+    }
+
+    #[test]
+    fn test_variants_are_collapsed() {
+        let server = Server::start();
+        let variant = |links: &str| {
+            Response::html(&format!(
+                r#"<link rel="canonical" href="/a">{}"#,
+                links
+            ))
+        };
+        server.route(
+            "/both",
+            vec![Response::html(&format!(
+                r#"<a href="{}">v</a><a href="{}">a</a>"#,
+                server.url("/v"),
+                server.url("/a")
+            ))],
+        );
+        server.route("/v", vec![variant("")]);
+        server.route(
+            "/w",
+            vec![variant(&format!(r#"<a href="{}">a</a>"#, server.url("/a")))],
+        );
+        server.page("/a", &[]);
+        server.page("/variant", &["/w"]);
+        let crawl = |seed: &str| {
+            let recorder = Recorder::default();
+            let summary = Crawler::new()
+                .register_event(Box::new(recorder.clone()))
+                .crawl_blocking(vec![Seed::new(server.url(seed)).depth(2)])
+                .unwrap();
+            let pages = recorder.0.lock().unwrap().clone();
+            let urls: Vec<_> = pages
+                .iter()
+                .map(|page| page.url.to_string())
+                .filter(|url| *url != server.url(seed))
+                .collect();
+
+            (summary, urls)
+        };
+
+        // The canonical page and its variant are both linked: only the
+        // former is passed to the events.
+        let (summary, urls) = crawl("/both");
+        assert_eq!(urls, vec![server.url("/a")]);
+        let fetched = |url: &String| {
+            summary
+                .fetched
+                .iter()
+                .filter(|fetched| *fetched == url)
+                .count()
+        };
+        assert_eq!(fetched(&server.url("/a")), 1);
+        assert_eq!(fetched(&server.url("/v")), 0);
+
+        // The variant is crawled first: it stands for its canonical page,
+        // which is not fetched anymore although the variant links to it.
+        let requests = server.requests().len();
+        let (summary, urls) = crawl("/variant");
+        assert_eq!(urls, vec![server.url("/w")]);
+        assert_eq!(server.requests()[requests..], ["/variant", "/w"]);
+        assert_eq!(
+            summary.fetched,
+            vec![server.url("/variant"), server.url("/a")]
+        );
+    }
+
+    #[test]
+    fn test_language() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(
+                r#"<html lang="en"><body><article><p>
+                Le crawler parcourt les pages du site, lit leur contenu et suit
+                les liens qu'elles contiennent afin de construire un index
+                complet de tous les documents qui y sont publiés. Chaque page
+                est analysée, son texte principal extrait et sa langue
+                identifiée avant d'être transmise aux événements.
+                </p></article></body></html>"#,
+            )
+            .header("Content-Language", "fr")],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .crawl_blocking([server.url("/")])
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        let language = pages[0].language.as_ref().unwrap();
+        assert_eq!(language.code, "fr");
+        assert_eq!(language.source, LanguageSource::Both);
+    }
+
+    #[test]
+    fn test_structured_data() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(
+                r#"<script type="application/ld+json">
+                {"@context": "https://schema.org", "@type": "Recipe"}
+                </script>
+                <div itemscope itemtype="https://schema.org/Product">
+                <span itemprop="name">Lampe</span>
+                </div>"#,
+            )],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(
+            pages[0]
+                .structured_data
+                .iter()
+                .map(|item| item.types())
+                .collect::<Vec<_>>(),
+            vec![vec!["Recipe"], vec!["Product"]]
+        );
+    }
+
+    #[test]
+    fn test_image() {
+        let server = Server::start();
+        server.route(
+            "/",
+            vec![Response::html(
+                r#"<figure>
+                <img src="/photo.jpg" alt="Tour" title="Tour Eiffel">
+                <figcaption>Paris de nuit</figcaption>
+                </figure>"#,
+            )],
+        );
+        server.route(
+            "/photo.jpg",
+            vec![Response::bytes(
+                "image/jpeg",
+                include_bytes!("../fixtures/photo.jpg"),
+            )],
+        );
+        let recorder = Recorder::default();
+
+        Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .fetch(server.url("/"))
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages[1].title.as_deref(), Some("Tour Eiffel"));
+        let Some(extractor::Document::Image(image)) = &pages[1].document else {
+            panic!("no image: {:?}", pages[1].document);
+        };
+        assert_eq!((image.width, image.height), (64, 48));
+        assert_eq!(image.exif.make.as_deref(), Some("Canon"));
+        assert_eq!(
+            image.context,
+            extractor::image::ImageContext {
+                alt: Some("Tour".to_owned()),
+                title: Some("Tour Eiffel".to_owned()),
+                caption: Some("Paris de nuit".to_owned()),
+                referrer: Some(pages[0].url.clone()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_office_document() {
+        let server = Server::start();
+        server.page("/", &["/report.docx"]);
+        server.route(
+            "/report.docx",
+            vec![Response::bytes(
+                "application/octet-stream",
+                include_bytes!("../fixtures/report.docx"),
+            )],
+        );
+        let recorder = Recorder::default();
+
+        let summary = Crawler::new()
+            .register_event(Box::new(recorder.clone()))
+            .crawl_blocking(vec![Seed::new(server.url("/"))
+                .allowed_domains(vec![r"^127\.0\.0\.1$".to_owned()])])
+            .unwrap();
+
+        let pages = recorder.0.lock().unwrap();
+        assert_eq!(pages[1].title.as_deref(), Some("Rapport annuel"));
+        let Some(extractor::Document::Office(office)) = &pages[1].document
+        else {
+            panic!("no office document: {:?}", pages[1].document);
+        };
+        assert!(office.text.starts_with("Rapport annuel\nLa vie seule"));
+
+        // Links of the document are queued like the ones of a page.
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].url, "https://www.gravitalia.com/");
+        assert!(matches!(summary.skipped[0].reason, SkipReason::OutOfScope));
+    }
+
+    #[test]
+    fn test_max_body_size() {
+        let server = Server::start();
+        server.route("/", vec![Response::html(&"a".repeat(2048))]);
+
+        let result = Crawler::new().max_body_size(1024).fetch(server.url("/"));
+        assert_eq!(error_type(result), CrawlerError::BodyTooLarge);
+
+        Crawler::new()
+            .max_body_size(2048)
+            .fetch(server.url("/"))
+            .unwrap();
     }
 }
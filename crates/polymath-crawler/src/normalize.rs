@@ -0,0 +1,261 @@
+//! URL canonicalization.
+//!
+//! Two URLs pointing to the same page should be crawled once. A
+//! [Normalizer] rewrites URLs to a canonical form:
+//! * scheme and host are lowercased, default ports are removed and dot
+//!   segments (`/a/../b`) are resolved;
+//! * the fragment is removed;
+//! * tracking parameters (`utm_*`, `fbclid`, `gclid`, ...) and session IDs
+//!   are removed;
+//! * query parameters are sorted by name.
+//!
+//! # Examples
+//! ```rust
+//! use polymath_crawler::normalize::Normalizer;
+//! use url::Url;
+//!
+//! let url = Url::parse("HTTP://Example.com:80/a/../b?utm_source=x#frag")
+//!     .unwrap();
+//!
+//! assert_eq!(
+//!     Normalizer::new().normalize(&url).as_str(),
+//!     "http://example.com/b"
+//! );
+//! ```
+
+use url::Url;
+
+/// Query parameters used to track visitors. A trailing `*` matches any
+/// suffix.
+const TRACKING_PARAMS: [&str; 12] = [
+    "utm_*", // Google Analytics
+    "fbclid", "gclid", "dclid", "gclsrc", "msclkid", "yclid", // Ads
+    "mc_cid", "mc_eid", // Mailchimp
+    "_ga", "_gl",    // Google cross-domain tracking
+    "igshid", // Instagram
+];
+
+/// Query parameters carrying a session ID.
+const SESSION_PARAMS: [&str; 7] = [
+    "jsessionid",
+    "phpsessid",
+    "aspsessionid*",
+    "sid",
+    "sessionid",
+    "session_id",
+    "cfid",
+];
+
+/// Rules used to rewrite URLs to a canonical form.
+#[derive(Debug, Clone)]
+pub struct Normalizer {
+    sort_query: bool,
+    strip_tracking: bool,
+    strip_session_ids: bool,
+    strip_params: Vec<String>,
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Normalizer {
+            sort_query: true,
+            strip_tracking: true,
+            strip_session_ids: true,
+            strip_params: Vec::new(),
+        }
+    }
+}
+
+impl Normalizer {
+    /// Create a [Normalizer] applying every rule.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets whether query parameters are sorted by name.
+    pub fn sort_query(mut self, sort_query: bool) -> Self {
+        self.sort_query = sort_query;
+        self
+    }
+
+    /// Sets whether known tracking parameters (`utm_*`, `fbclid`, `gclid`,
+    /// ...) are removed.
+    pub fn strip_tracking(mut self, strip_tracking: bool) -> Self {
+        self.strip_tracking = strip_tracking;
+        self
+    }
+
+    /// Sets whether session IDs (`jsessionid`, `phpsessid`, `sid`, ...) are
+    /// removed, from the query as well as from the path.
+    pub fn strip_session_ids(mut self, strip_session_ids: bool) -> Self {
+        self.strip_session_ids = strip_session_ids;
+        self
+    }
+
+    /// Adds query parameters to remove. Names are case-insensitive and a
+    /// trailing `*` matches any suffix (`ref_*`).
+    pub fn strip_params(mut self, params: Vec<String>) -> Self {
+        self.strip_params.extend(params);
+        self
+    }
+
+    /// Rewrite a URL to its canonical form.
+    pub fn normalize(&self, url: &Url) -> Url {
+        // Parsing already lowercased scheme and host, removed default port
+        // and resolved dot segments.
+        let mut url = url.clone();
+        url.set_fragment(None);
+
+        if self.strip_session_ids && url.path().contains(';') {
+            let path = url
+                .path()
+                .split('/')
+                .map(|segment| match segment.split_once(';') {
+                    Some((segment, param))
+                        if self.is_session(param.split('=').next()) =>
+                    {
+                        segment
+                    },
+                    _ => segment,
+                })
+                .collect::<Vec<_>>()
+                .join("/");
+            url.set_path(&path);
+        }
+
+        if let Some(query) = url.query() {
+            let mut params = query
+                .split('&')
+                .filter(|param| !param.is_empty())
+                .filter(|param| !self.is_stripped(param.split('=').next()))
+                .map(str::to_owned)
+                .collect::<Vec<_>>();
+
+            if self.sort_query {
+                // Stable sort: repeated parameters keep their order.
+                params.sort_by(|a, b| {
+                    a.split('=').next().cmp(&b.split('=').next())
+                });
+            }
+
+            if params.is_empty() {
+                url.set_query(None);
+            } else {
+                url.set_query(Some(&params.join("&")));
+            }
+        }
+
+        url
+    }
+
+    fn is_session(&self, name: Option<&str>) -> bool {
+        name.is_some_and(|name| matches(&SESSION_PARAMS, name))
+    }
+
+    fn is_stripped(&self, name: Option<&str>) -> bool {
+        name.is_some_and(|name| {
+            (self.strip_tracking && matches(&TRACKING_PARAMS, name)) ||
+                (self.strip_session_ids && matches(&SESSION_PARAMS, name)) ||
+                matches(&self.strip_params, name)
+        })
+    }
+}
+
+/// Whether a parameter name matches one of the patterns.
+fn matches<T: AsRef<str>>(patterns: &[T], name: &str) -> bool {
+    let name = name.to_lowercase();
+
+    patterns.iter().any(|pattern| {
+        let pattern = pattern.as_ref().to_lowercase();
+        match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalize(normalizer: &Normalizer, url: &str) -> String {
+        normalizer.normalize(&Url::parse(url).unwrap()).to_string()
+    }
+
+    #[test]
+    fn test_normalize() {
+        let normalizer = Normalizer::new();
+
+        assert_eq!(
+            normalize(
+                &normalizer,
+                "HTTP://Example.com:80/a/../b?utm_source=x#frag"
+            ),
+            "http://example.com/b"
+        );
+        assert_eq!(
+            normalize(&normalizer, "https://example.com:443/./a/b/../c/"),
+            "https://example.com/a/c/"
+        );
+        assert_eq!(
+            normalize(&normalizer, "https://example.com:8443/"),
+            "https://example.com:8443/"
+        );
+    }
+
+    #[test]
+    fn test_query() {
+        let normalizer = Normalizer::new();
+
+        assert_eq!(
+            normalize(
+                &normalizer,
+                "https://example.com/?q=rust&fbclid=1&page=2&GCLID=2&a=%20"
+            ),
+            "https://example.com/?a=%20&page=2&q=rust"
+        );
+        assert_eq!(
+            normalize(&normalizer, "https://example.com/?tag=b&id=1&tag=a"),
+            "https://example.com/?id=1&tag=b&tag=a"
+        );
+        assert_eq!(
+            normalize(&normalizer, "https://example.com/?"),
+            "https://example.com/"
+        );
+    }
+
+    #[test]
+    fn test_session_ids() {
+        let normalizer = Normalizer::new();
+
+        assert_eq!(
+            normalize(
+                &normalizer,
+                "https://example.com/shop;jsessionid=A1B2/cart?PHPSESSID=42&sid=1"
+            ),
+            "https://example.com/shop/cart"
+        );
+
+        let normalizer = Normalizer::new().strip_session_ids(false);
+        assert_eq!(
+            normalize(&normalizer, "https://example.com/cart?sid=1"),
+            "https://example.com/cart?sid=1"
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let normalizer = Normalizer::new()
+            .sort_query(false)
+            .strip_tracking(false)
+            .strip_params(vec!["ref_*".to_owned()]);
+
+        assert_eq!(
+            normalize(
+                &normalizer,
+                "https://example.com/?z=1&utm_medium=mail&ref_src=tw&a=2"
+            ),
+            "https://example.com/?z=1&utm_medium=mail&a=2"
+        );
+    }
+}
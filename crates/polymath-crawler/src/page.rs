@@ -0,0 +1,139 @@
+//! Pages fetched by the crawler.
+
+use std::borrow::Cow;
+use std::time::{Duration, SystemTime};
+
+use encoding_rs::Encoding;
+use url::Url;
+
+use crate::extractor::article::Article;
+use crate::extractor::canonical::LanguageCluster;
+use crate::extractor::language::Language;
+use crate::extractor::link::Link;
+use crate::extractor::meta::{Meta, PageMetadata};
+use crate::extractor::structured::StructuredData;
+use crate::extractor::Document;
+use crate::{content, encoding};
+
+/// Page fetched by the [Crawler](crate::Crawler), passed to
+/// [Event::after_request](crate::Event::after_request).
+#[derive(Debug, Clone)]
+pub struct FetchedPage {
+    /// URL requested by the crawler.
+    pub url: Url,
+    /// URL of the response, once redirects are followed.
+    pub final_url: Url,
+    /// URLs which redirected to [final_url](FetchedPage::final_url),
+    /// starting with the requested URL. Empty if there was no redirect.
+    pub redirects: Vec<Url>,
+    /// HTTP status code of the response.
+    pub status: u16,
+    /// Response headers, in order, with lowercase names.
+    pub headers: Vec<(String, String)>,
+    /// Media type of the response (such as `text/html`), without its
+    /// parameters.
+    pub content_type: Option<String>,
+    /// Raw response body.
+    pub body: Vec<u8>,
+    /// Character encoding of a text body (such as `Shift_JIS`), from its
+    /// headers, byte order mark, `<meta>` tags or content. `None` for
+    /// binary resources.
+    pub encoding: Option<String>,
+    /// When the response was received.
+    pub fetched_at: SystemTime,
+    /// Time spent sending the request and reading the response, from the
+    /// last redirect on.
+    pub fetch_time: Duration,
+    /// Number of hops from the seed.
+    pub depth: usize,
+    /// Page on which the URL was found. `None` for seeds.
+    pub referrer: Option<Url>,
+    /// Link through which the URL was found on the
+    /// [referrer](FetchedPage::referrer). `None` for seeds.
+    pub link: Option<Link>,
+    /// Content of the `<title>` tag.
+    pub title: Option<String>,
+    /// [`<meta>`](https://developer.mozilla.org/en-US/docs/Web/HTML/Element/meta)
+    /// tags of the page.
+    pub meta: Vec<Meta>,
+    /// Typed view of the [meta](FetchedPage::meta) tags and `<head>` of an
+    /// HTML page.
+    pub metadata: Option<PageMetadata>,
+    /// Canonical URL of the page, from its `<link rel="canonical">` or,
+    /// failing that, its `Link` header. Pages sharing a canonical URL are
+    /// duplicates of it, which are crawled only once. See
+    /// [skip_non_canonical](crate::Crawler::skip_non_canonical).
+    pub canonical: Option<Url>,
+    /// Translations of the page, from its hreflang `<link>` tags and `Link`
+    /// header.
+    pub language_cluster: Option<LanguageCluster>,
+    /// Language of the page, declared by it or detected from its text.
+    pub language: Option<Language>,
+    /// Main content of an HTML page, without its navigation, footers, ads
+    /// and scripts.
+    pub article: Option<Article>,
+    /// JSON-LD, Microdata and RDFa items of the page, such as schema.org
+    /// products or events.
+    pub structured_data: Vec<StructuredData>,
+    /// Text and metadata of a resource other than an HTML page, such as a
+    /// PDF document.
+    pub document: Option<Document>,
+}
+
+impl FetchedPage {
+    /// Value of the first response header with a name, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Response body as text, decoded from its
+    /// [encoding](FetchedPage::encoding). Malformed sequences are replaced.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self
+            .encoding
+            .as_deref()
+            .and_then(|label| Encoding::for_label(label.as_bytes()))
+        {
+            Some(encoding) => encoding::decode(&self.body, encoding),
+            None => String::from_utf8_lossy(&self.body),
+        }
+    }
+
+    /// Whether the response is text, such as an HTML page. Without
+    /// `Content-Type`, the body is assumed to be text.
+    pub fn is_text(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_none_or(content::is_text_type)
+    }
+
+    /// Whether the response is an HTML page. Without `Content-Type`, the
+    /// start of the body is sniffed.
+    pub fn is_html(&self) -> bool {
+        match &self.content_type {
+            Some(media_type) => content::is_page_type(media_type),
+            None => {
+                let start = &self.body[..self.body.len().min(512)];
+                let start = String::from_utf8_lossy(start).to_lowercase();
+                let start = start.trim_start_matches('\u{feff}').trim_start();
+
+                start.starts_with("<!doctype html") ||
+                    start.starts_with("<html")
+            },
+        }
+    }
+
+    /// Whether the response is a PDF document.
+    pub fn is_pdf(&self) -> bool {
+        self.content_type.as_deref() == Some("application/pdf") ||
+            self.body.starts_with(b"%PDF-")
+    }
+
+    /// Whether the crawler was redirected to another URL.
+    pub fn is_redirected(&self) -> bool {
+        !self.redirects.is_empty()
+    }
+}
@@ -0,0 +1,291 @@
+//! Per-host request spacing.
+//!
+//! [Politeness] remembers when each host was last requested and how long
+//! the crawler must wait before requesting it again. The delay starts at a
+//! configurable minimum, replaced by the robots.txt `Crawl-delay` of the
+//! host when there is one, and adapts to the host health:
+//! * `429 Too Many Requests` and `503 Service Unavailable` double it;
+//! * a response slower than the delay raises it to the response time;
+//! * fast responses bring it back towards the base delay.
+//!
+//! # Examples
+//! ```rust
+//! use std::time::Duration;
+//!
+//! use polymath_crawler::politeness::Politeness;
+//! use polymath_crawler::Crawler;
+//!
+//! // Wait at least 500ms between two requests on the same host.
+//! let crawler = Crawler::new()
+//!     .politeness(Politeness::new(Duration::from_millis(500)));
+//! ```
+
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+/// Future returned by [Clock::sleep].
+pub type Sleep = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// Source of time used by the crawler, to be replaced in tests.
+pub trait Clock: Debug + Send + Sync {
+    /// Current instant.
+    fn now(&self) -> Instant;
+    /// Wait for a duration without blocking the current thread.
+    fn sleep(&self, duration: Duration) -> Sleep;
+}
+
+/// [Clock] using the system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) -> Sleep {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+impl Default for Box<dyn Clock> {
+    fn default() -> Self {
+        Box::new(SystemClock)
+    }
+}
+
+/// Politeness state of a host.
+#[derive(Debug, Clone, Default)]
+struct Host {
+    /// Delay asked by the host robots.txt.
+    crawl_delay: Option<Duration>,
+    /// Current delay between two requests.
+    delay: Duration,
+    /// Instant of the end of the last request.
+    last_request: Option<Instant>,
+}
+
+/// Spacing of the requests sent to each host.
+#[derive(Debug, Clone)]
+pub struct Politeness {
+    hosts: HashMap<String, Host>,
+    max_delay: Duration,
+    min_delay: Duration,
+}
+
+impl Default for Politeness {
+    fn default() -> Self {
+        Politeness::new(Duration::ZERO)
+    }
+}
+
+impl Politeness {
+    /// Create a [Politeness] waiting at least `min_delay` between two
+    /// requests on the same host.
+    pub fn new(min_delay: Duration) -> Self {
+        Politeness {
+            hosts: HashMap::new(),
+            max_delay: Duration::from_secs(60),
+            min_delay,
+        }
+    }
+
+    /// Sets the upper bound of the adaptive delay. A robots.txt
+    /// `Crawl-delay` above this bound is still obeyed.
+    pub fn max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the `Crawl-delay` asked by a host, if any.
+    pub fn set_crawl_delay(
+        &mut self,
+        host: &str,
+        crawl_delay: Option<Duration>,
+    ) {
+        let min_delay = self.min_delay;
+        let state = self.hosts.entry(host.to_owned()).or_default();
+
+        if state.crawl_delay != crawl_delay {
+            state.crawl_delay = crawl_delay;
+            state.delay = crawl_delay.unwrap_or(min_delay);
+        }
+    }
+
+    /// Current delay between two requests on a host.
+    pub fn delay(&self, host: &str) -> Duration {
+        self.hosts
+            .get(host)
+            .map(|state| state.delay.max(self.base_delay(state)))
+            .unwrap_or(self.min_delay)
+    }
+
+    /// How long to wait at `now` before requesting a host.
+    pub fn wait_time(&self, host: &str, now: Instant) -> Duration {
+        self.hosts
+            .get(host)
+            .and_then(|state| state.last_request)
+            .map(|last_request| {
+                (last_request + self.delay(host)).saturating_duration_since(now)
+            })
+            .unwrap_or_default()
+    }
+
+    /// Book the next request slot of a host and return how long to wait at
+    /// `now` before sending it. Concurrent requests to the same host are
+    /// spaced out by the host delay.
+    pub fn reserve(&mut self, host: &str, now: Instant) -> Duration {
+        let wait = self.wait_time(host, now);
+        self.hosts.entry(host.to_owned()).or_default().last_request =
+            Some(now + wait);
+
+        wait
+    }
+
+    /// Record a request on a host, from `start` to `end`, and adapt the
+    /// delay to the response `status` (if any) and time.
+    pub fn record(
+        &mut self,
+        host: &str,
+        start: Instant,
+        end: Instant,
+        status: Option<u16>,
+    ) {
+        let delay = self.delay(host);
+        let max_delay = self.max_delay;
+        let state = self.hosts.entry(host.to_owned()).or_default();
+        let base = state.crawl_delay.unwrap_or(self.min_delay);
+        let response_time = end.saturating_duration_since(start);
+
+        // A request booked by reserve may start after this one ended.
+        state.last_request = state.last_request.max(Some(end));
+        state.delay = if matches!(status, Some(429 | 503)) {
+            (delay * 2)
+                .max(Duration::from_secs(1))
+                .min(max_delay.max(base))
+        } else if response_time > delay {
+            response_time.min(max_delay.max(base))
+        } else {
+            // Move halfway back to the base delay.
+            (delay + base) / 2
+        };
+    }
+
+    fn base_delay(&self, state: &Host) -> Duration {
+        state.crawl_delay.unwrap_or(self.min_delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_delay() {
+        let mut politeness = Politeness::new(Duration::from_secs(2));
+        let now = Instant::now();
+
+        assert_eq!(politeness.wait_time("gravitalia.com", now), Duration::ZERO);
+        politeness.record("gravitalia.com", now, now, Some(200));
+
+        assert_eq!(
+            politeness.wait_time("gravitalia.com", now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            politeness
+                .wait_time("gravitalia.com", now + Duration::from_secs(1)),
+            Duration::from_secs(1)
+        );
+        // Other hosts are not affected.
+        assert_eq!(politeness.wait_time("lubmminy.com", now), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_reserve() {
+        let mut politeness = Politeness::new(Duration::from_secs(2));
+        let now = Instant::now();
+
+        assert_eq!(politeness.reserve("gravitalia.com", now), Duration::ZERO);
+        assert_eq!(
+            politeness.reserve("gravitalia.com", now),
+            Duration::from_secs(2)
+        );
+        assert_eq!(
+            politeness.reserve("gravitalia.com", now),
+            Duration::from_secs(4)
+        );
+
+        // The first request ending does not free the booked slots.
+        politeness.record(
+            "gravitalia.com",
+            now,
+            now + Duration::from_secs(1),
+            Some(200),
+        );
+        assert_eq!(
+            politeness.wait_time("gravitalia.com", now),
+            Duration::from_secs(6)
+        );
+    }
+
+    #[test]
+    fn test_crawl_delay() {
+        let mut politeness = Politeness::new(Duration::from_secs(2))
+            .max_delay(Duration::from_secs(5));
+        let now = Instant::now();
+
+        politeness
+            .set_crawl_delay("gravitalia.com", Some(Duration::from_secs(10)));
+        politeness.record("gravitalia.com", now, now, Some(200));
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::from_secs(10));
+
+        politeness.set_crawl_delay("gravitalia.com", Some(Duration::ZERO));
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::ZERO);
+    }
+
+    #[test]
+    fn test_backoff() {
+        let mut politeness = Politeness::new(Duration::from_secs(1))
+            .max_delay(Duration::from_secs(10));
+        let now = Instant::now();
+
+        politeness.record("gravitalia.com", now, now, Some(503));
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::from_secs(2));
+        politeness.record("gravitalia.com", now, now, Some(429));
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::from_secs(4));
+        politeness.record("gravitalia.com", now, now, Some(429));
+        politeness.record("gravitalia.com", now, now, Some(429));
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::from_secs(10));
+
+        // Healthy responses bring the delay back.
+        politeness.record("gravitalia.com", now, now, Some(200));
+        assert_eq!(
+            politeness.delay("gravitalia.com"),
+            Duration::from_millis(5500)
+        );
+        for _ in 0..10 {
+            politeness.record("gravitalia.com", now, now, Some(200));
+        }
+        assert!(
+            politeness.delay("gravitalia.com") < Duration::from_millis(1010)
+        );
+    }
+
+    #[test]
+    fn test_slow_host() {
+        let mut politeness = Politeness::new(Duration::from_secs(1));
+        let now = Instant::now();
+
+        politeness.record(
+            "gravitalia.com",
+            now,
+            now + Duration::from_secs(3),
+            Some(200),
+        );
+        assert_eq!(politeness.delay("gravitalia.com"), Duration::from_secs(3));
+    }
+}
@@ -0,0 +1,86 @@
+//! Retry policy of failed requests.
+
+use std::time::{Duration, SystemTime};
+
+/// Upper bound of the delay between two attempts.
+pub(crate) const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+/// Delay before the retry number `attempt` (starting at 0): `base` doubled
+/// on each attempt, with a random jitter of up to half the delay.
+pub(crate) fn backoff(base: Duration, attempt: u32) -> Duration {
+    let delay = base
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let jitter = delay.mul_f64(fastrand::f64() / 2.0);
+
+    delay - jitter
+}
+
+/// Delay asked by the
+/// [`Retry-After`](https://developer.mozilla.org/en-US/docs/Web/HTTP/Headers/Retry-After)
+/// header of a failed response, if any.
+pub(crate) fn retry_after(error: &polymath_error::Error) -> Option<Duration> {
+    let cause = error.cause.as_ref()?.downcast_ref::<ureq::Error>()?;
+    let ureq::Error::Status(_, response) = cause else {
+        return None;
+    };
+    let value = response.header("Retry-After")?.trim();
+
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => httpdate::parse_http_date(value).ok().map(|date| {
+            date.duration_since(SystemTime::now()).unwrap_or_default()
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(status: u16, retry_after: Option<&str>) -> polymath_error::Error {
+        let mut response = format!("HTTP/1.1 {} Status\r\n", status);
+        if let Some(retry_after) = retry_after {
+            response.push_str(&format!("Retry-After: {}\r\n", retry_after));
+        }
+        response.push_str("\r\n");
+
+        polymath_error::Error::new(
+            polymath_error::ErrorType::Crawler(
+                polymath_error::CrawlerError::HttpStatus(status),
+            ),
+            Some(Box::new(ureq::Error::Status(
+                status,
+                response.parse().unwrap(),
+            ))),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_backoff() {
+        let base = Duration::from_secs(2);
+
+        for attempt in 0..4 {
+            let delay = base * 2u32.pow(attempt);
+            let backoff = backoff(base, attempt);
+            assert!(backoff <= delay && backoff >= delay / 2, "{:?}", backoff);
+        }
+
+        assert!(backoff(base, 30) <= MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_retry_after() {
+        assert_eq!(
+            retry_after(&status(503, Some("120"))),
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            retry_after(&status(503, Some("Wed, 21 Oct 2015 07:28:00 GMT"))),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(retry_after(&status(503, Some("soon"))), None);
+        assert_eq!(retry_after(&status(503, None)), None);
+    }
+}
@@ -0,0 +1,110 @@
+//! Dispatch of the frontier URLs to concurrent visits.
+
+use std::collections::HashMap;
+
+use polymath_queue::Frontier;
+use url::Url;
+
+/// Key identifying a host: its name and explicit port, if any.
+pub(crate) fn host(url: &Url) -> String {
+    match url.port() {
+        Some(port) => {
+            format!("{}:{}", url.host_str().unwrap_or_default(), port)
+        },
+        None => url.host_str().unwrap_or_default().to_owned(),
+    }
+}
+
+fn host_of(url: &str) -> String {
+    Url::parse(url).map(|url| host(&url)).unwrap_or_default()
+}
+
+/// Picks the next URL to visit, so that a host never has more than
+/// `per_host` visits in progress.
+///
+/// URLs must be pushed to the group of their [host] in the frontier. URLs
+/// of busy hosts are skipped without leaving the frontier, so a busy host
+/// does not hold back the others.
+#[derive(Debug)]
+pub(crate) struct Scheduler {
+    active: HashMap<String, usize>,
+    per_host: usize,
+}
+
+impl Scheduler {
+    /// Create a [Scheduler] allowing `per_host` visits per host.
+    pub(crate) fn new(per_host: usize) -> Self {
+        Scheduler {
+            active: HashMap::new(),
+            per_host: per_host.max(1),
+        }
+    }
+
+    /// Next URL which can be visited right now, if any. The visit must be
+    /// reported with [finish](Scheduler::finish) once over.
+    pub(crate) fn next<T>(
+        &mut self,
+        frontier: &mut Frontier<T>,
+    ) -> Option<(String, T)> {
+        let (url, item) = frontier.pop_where(|host| {
+            self.active.get(host).copied().unwrap_or_default() < self.per_host
+        })?;
+        *self.active.entry(host_of(&url)).or_default() += 1;
+
+        Some((url, item))
+    }
+
+    /// Report the end of the visit of a URL returned by
+    /// [next](Scheduler::next).
+    pub(crate) fn finish(&mut self, url: &str) {
+        let host = host_of(url);
+
+        if let Some(active) = self.active.get_mut(&host) {
+            *active -= 1;
+            if *active == 0 {
+                self.active.remove(&host);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_per_host_limit() {
+        let mut frontier = Frontier::default();
+        for url in [
+            "https://gravitalia.com/a",
+            "https://gravitalia.com/b",
+            "https://lubmminy.com/",
+        ] {
+            frontier.push_to_group(host_of(url), url.to_owned(), (), 0);
+        }
+        let mut scheduler = Scheduler::new(1);
+
+        let (first, _) = scheduler.next(&mut frontier).unwrap();
+        assert_eq!(first, "https://gravitalia.com/a");
+        // gravitalia.com is busy: its next URL is skipped.
+        let (second, _) = scheduler.next(&mut frontier).unwrap();
+        assert_eq!(second, "https://lubmminy.com/");
+        assert!(scheduler.next(&mut frontier).is_none());
+
+        scheduler.finish(&first);
+        let (third, _) = scheduler.next(&mut frontier).unwrap();
+        assert_eq!(third, "https://gravitalia.com/b");
+    }
+
+    #[test]
+    fn test_ports_are_distinct_hosts() {
+        assert_eq!(
+            host(&Url::parse("http://127.0.0.1:8080/").unwrap()),
+            "127.0.0.1:8080"
+        );
+        assert_eq!(
+            host(&Url::parse("https://gravitalia.com:443/").unwrap()),
+            "gravitalia.com"
+        );
+    }
+}
@@ -0,0 +1,73 @@
+//! Starting points of a crawl.
+//!
+//! # Examples
+//! ```rust,no_run
+//! use polymath_crawler::{Crawler, Seed};
+//!
+//! let mut crawler = Crawler::new().depth(1);
+//! let summary = crawler
+//!     .crawl_blocking(vec![
+//!         // Go deeper on gravitalia.com, without leaving it.
+//!         Seed::new("https://gravitalia.com/")
+//!             .depth(3)
+//!             .allowed_domains(vec![r"(^|\.)gravitalia\.com$".to_owned()]),
+//!         // Use the crawler settings.
+//!         Seed::from("https://lubmminy.com/"),
+//!     ])
+//!     .unwrap();
+//!
+//! println!("{} pages fetched", summary.fetched.len());
+//! ```
+
+/// Starting URL of a crawl, with its own depth budget and scope.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Seed {
+    pub(crate) url: String,
+    pub(crate) depth: Option<usize>,
+    pub(crate) allowed_domains: Vec<String>,
+}
+
+impl Seed {
+    /// Create a [Seed] using the depth of the [Crawler](crate::Crawler)
+    /// and no scope.
+    pub fn new(url: impl Into<String>) -> Self {
+        Seed {
+            url: url.into(),
+            depth: None,
+            allowed_domains: Vec::new(),
+        }
+    }
+
+    /// Sets the maximum depth of the pages crawled from this seed, in place
+    /// of the [crawler depth](crate::Crawler::depth).
+    pub fn depth(mut self, depth: usize) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    /// Restricts the pages crawled from this seed to some domains, on top of
+    /// the [crawler allowed domains](crate::Crawler::allowed_domains).
+    ///
+    /// Domains use the same regular expression patterns as the crawler.
+    pub fn allowed_domains(mut self, domains: Vec<String>) -> Self {
+        self.allowed_domains = domains;
+        self
+    }
+
+    /// Starting URL.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl From<&str> for Seed {
+    fn from(url: &str) -> Self {
+        Seed::new(url)
+    }
+}
+
+impl From<String> for Seed {
+    fn from(url: String) -> Self {
+        Seed::new(url)
+    }
+}
@@ -0,0 +1,207 @@
+//! Remember which URLs were already queued during a crawl.
+//!
+//! The [Crawler](crate::Crawler) keeps every discovered URL in a [Seen]
+//! backend and skips the ones it already knows. Two backends are provided:
+//! * [MemorySet] keeps every URL in a [HashSet] and never gives a wrong
+//!   answer;
+//! * [BloomFilter] uses a fixed amount of memory, at the cost of skipping
+//!   a small fraction of never-seen URLs (false positives).
+//!
+//! # Examples
+//! ```rust
+//! use polymath_crawler::seen::{BloomFilter, Seen};
+//! use polymath_crawler::Crawler;
+//!
+//! // Allow 1 wrongly skipped URL out of 1000 for 10 million pages.
+//! let crawler = Crawler::new()
+//!     .seen(Box::new(BloomFilter::new(10_000_000, 0.001)));
+//! ```
+
+use std::collections::HashSet;
+use std::fmt::Debug;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Storage of the URLs already seen by the crawler.
+pub trait Seen: Debug + Send + Sync {
+    /// Mark a URL as seen.
+    ///
+    /// Returns `true` if the URL had not been seen before.
+    fn insert(&mut self, url: &str) -> bool;
+    /// Whether a URL has already been seen.
+    fn contains(&self, url: &str) -> bool;
+    /// Number of URLs marked as seen.
+    fn len(&self) -> usize;
+    /// Whether no URL has been seen yet.
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Box<dyn Seen> {
+    fn default() -> Self {
+        Box::new(MemorySet::default())
+    }
+}
+
+/// Exact in-memory [Seen] backend.
+#[derive(Debug, Default)]
+pub struct MemorySet {
+    urls: HashSet<String>,
+}
+
+impl MemorySet {
+    /// Create an empty [MemorySet].
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl Seen for MemorySet {
+    fn insert(&mut self, url: &str) -> bool {
+        self.urls.insert(url.to_owned())
+    }
+
+    fn contains(&self, url: &str) -> bool {
+        self.urls.contains(url)
+    }
+
+    fn len(&self) -> usize {
+        self.urls.len()
+    }
+}
+
+/// Memory-bounded probabilistic [Seen] backend.
+///
+/// A Bloom filter never forgets a URL, but may claim an unseen URL was
+/// already seen. The probability of such a false positive depends on the
+/// number of bits and hash functions, see [BloomFilter::new].
+#[derive(Debug)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    size: u64,
+    hashes: u32,
+    count: usize,
+}
+
+impl BloomFilter {
+    /// Create a [BloomFilter] sized to hold `expected_items` URLs with a
+    /// false positive rate of `false_positive_rate` (between 0 and 1).
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        let items = expected_items.max(1) as f64;
+        let rate = false_positive_rate.clamp(f64::MIN_POSITIVE, 0.5);
+        let ln2 = std::f64::consts::LN_2;
+
+        let size = (-items * rate.ln() / (ln2 * ln2)).ceil() as u64;
+        let hashes = ((size as f64 / items) * ln2).round() as u32;
+
+        Self::with_size(size, hashes)
+    }
+
+    /// Create a [BloomFilter] using `size` bits and `hashes` hash functions.
+    pub fn with_size(size: u64, hashes: u32) -> Self {
+        let size = size.max(64);
+
+        BloomFilter {
+            bits: vec![0; size.div_ceil(64) as usize],
+            size,
+            hashes: hashes.max(1),
+            count: 0,
+        }
+    }
+
+    /// Memory used by the filter bits, in bytes.
+    pub fn memory(&self) -> usize {
+        self.bits.len() * std::mem::size_of::<u64>()
+    }
+
+    /// Positions of a URL in the bit array, using double hashing.
+    fn positions(&self, url: &str) -> impl Iterator<Item = u64> {
+        let mut hasher = DefaultHasher::new();
+        url.hash(&mut hasher);
+        let first = hasher.finish();
+        // Seed the second hash with the first one so both are independent.
+        first.hash(&mut hasher);
+        let second = hasher.finish() | 1;
+
+        let size = self.size;
+        (0..u64::from(self.hashes))
+            .map(move |i| first.wrapping_add(i.wrapping_mul(second)) % size)
+    }
+}
+
+impl Seen for BloomFilter {
+    fn insert(&mut self, url: &str) -> bool {
+        let mut inserted = false;
+
+        for position in self.positions(url).collect::<Vec<_>>() {
+            let (index, mask) =
+                ((position / 64) as usize, 1 << (position % 64));
+            if self.bits[index] & mask == 0 {
+                self.bits[index] |= mask;
+                inserted = true;
+            }
+        }
+
+        if inserted {
+            self.count += 1;
+        }
+
+        inserted
+    }
+
+    fn contains(&self, url: &str) -> bool {
+        self.positions(url).all(|position| {
+            self.bits[(position / 64) as usize] & (1 << (position % 64)) != 0
+        })
+    }
+
+    fn len(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_memory_set() {
+        let mut seen = MemorySet::new();
+        assert!(seen.is_empty());
+
+        assert!(seen.insert("https://gravitalia.com/"));
+        assert!(!seen.insert("https://gravitalia.com/"));
+        assert!(seen.contains("https://gravitalia.com/"));
+        assert!(!seen.contains("https://news.gravitalia.com/"));
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn test_bloom_filter() {
+        let mut seen = BloomFilter::new(1000, 0.01);
+
+        assert!(seen.insert("https://gravitalia.com/"));
+        assert!(!seen.insert("https://gravitalia.com/"));
+        assert!(seen.contains("https://gravitalia.com/"));
+        assert_eq!(seen.len(), 1);
+    }
+
+    #[test]
+    fn test_bloom_filter_false_positives() {
+        let mut seen = BloomFilter::new(1000, 0.01);
+        // 1000 items at 1% need 9586 bits.
+        assert_eq!(seen.memory(), 1200);
+
+        for i in 0..1000 {
+            seen.insert(&format!("https://gravitalia.com/{}", i));
+        }
+        for i in 0..1000 {
+            assert!(seen.contains(&format!("https://gravitalia.com/{}", i)));
+        }
+
+        let false_positives = (0..10_000)
+            .filter(|i| seen.contains(&format!("https://lubmminy.com/{}", i)))
+            .count();
+        assert!(false_positives < 300, "{} false positives", false_positives);
+    }
+}
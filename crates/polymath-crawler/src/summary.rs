@@ -0,0 +1,101 @@
+//! Outcome of a crawl.
+
+use std::fmt;
+use std::time::Duration;
+
+/// Why a URL was not fetched, or its page not passed to the
+/// [events](crate::Event).
+#[derive(Debug)]
+pub enum SkipReason {
+    /// The seed was already crawled, by this crawl or a previous one.
+    AlreadySeen,
+    /// The URL is deeper than the depth budget of its seed.
+    MaxDepth,
+    /// The link is marked `nofollow`, `ugc` or `sponsored`.
+    NoFollow,
+    /// An [Event](crate::Event) dropped the link in
+    /// [on_link_discovered](crate::Event::on_link_discovered).
+    Filtered,
+    /// The URL is outside the allowed domains of its seed.
+    OutOfScope,
+    /// The crawler allowed domains or an [Event](crate::Event) refused the
+    /// URL, such as a robots.txt disallowing it.
+    Rejected(polymath_error::Error),
+    /// The URL extension is not one of the allowed extensions.
+    Extension(String),
+    /// The response media type is neither HTML nor the one of an allowed
+    /// extension.
+    ContentType(String),
+    /// The page is a variant of this canonical URL, which is crawled
+    /// instead or already was. See
+    /// [skip_non_canonical](crate::Crawler::skip_non_canonical).
+    NonCanonical(String),
+    /// The URL redirects to this one, which is crawled instead if allowed.
+    Redirected(String),
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkipReason::AlreadySeen => {
+                write!(f, "The URL was already crawled.")
+            },
+            SkipReason::MaxDepth => write!(f, "The maximum depth is reached."),
+            SkipReason::NoFollow => write!(f, "The link is marked nofollow."),
+            SkipReason::Filtered => {
+                write!(f, "The link was dropped by an event.")
+            },
+            SkipReason::OutOfScope => {
+                write!(f, "The URL is outside the seed allowed domains.")
+            },
+            SkipReason::Rejected(error) => write!(f, "{}", error),
+            SkipReason::Extension(extension) => {
+                write!(f, "The .{} extension is not allowed.", extension)
+            },
+            SkipReason::ContentType(media_type) => {
+                write!(f, "The {} content type is not allowed.", media_type)
+            },
+            SkipReason::NonCanonical(canonical) => {
+                write!(f, "The page is a variant of {}.", canonical)
+            },
+            SkipReason::Redirected(location) => {
+                write!(f, "The URL redirects to {}.", location)
+            },
+        }
+    }
+}
+
+/// URL which was not fetched.
+#[derive(Debug)]
+pub struct Skipped {
+    /// Skipped URL.
+    pub url: String,
+    /// Number of hops from the seed.
+    pub depth: usize,
+    /// Why the URL was not fetched.
+    pub reason: SkipReason,
+}
+
+/// URL which could not be crawled.
+#[derive(Debug)]
+pub struct Failure {
+    /// Failed URL.
+    pub url: String,
+    /// Number of hops from the seed.
+    pub depth: usize,
+    /// Cause of the failure.
+    pub error: polymath_error::Error,
+}
+
+/// Pages fetched, failed and skipped by a crawl.
+#[derive(Debug, Default)]
+pub struct CrawlSummary {
+    /// URLs successfully fetched.
+    pub fetched: Vec<String>,
+    /// URLs which could not be crawled.
+    pub failed: Vec<Failure>,
+    /// URLs discovered but not fetched.
+    pub skipped: Vec<Skipped>,
+    /// Duration of the crawl.
+    pub elapsed: Duration,
+}
@@ -0,0 +1,288 @@
+//! Minimal HTTP server used by the crawler tests.
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Canned HTTP response.
+#[derive(Debug, Clone)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub delay: Duration,
+}
+
+impl Response {
+    /// `200 OK` response with an HTML body.
+    pub fn html(body: &str) -> Self {
+        Response {
+            status: 200,
+            headers: vec![(
+                "Content-Type".to_owned(),
+                "text/html; charset=utf-8".to_owned(),
+            )],
+            body: body.as_bytes().to_vec(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// `200 OK` response with any body.
+    pub fn bytes(content_type: &str, body: &[u8]) -> Self {
+        Response {
+            status: 200,
+            headers: vec![("Content-Type".to_owned(), content_type.to_owned())],
+            body: body.to_vec(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Empty response with a specific status code.
+    pub fn status(status: u16) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: Vec::new(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    /// Add a header to the response.
+    pub fn header(mut self, key: &str, value: &str) -> Self {
+        self.headers.push((key.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Wait before sending the response.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    routes: HashMap<String, Vec<Response>>,
+    requests: Vec<String>,
+    active: usize,
+    max_active: usize,
+}
+
+/// HTTP server listening on a random local port.
+///
+/// Each route holds a list of responses served in order; the last one is
+/// repeated once the others have been consumed. Unknown paths get a `404`.
+#[derive(Debug)]
+pub(crate) struct Server {
+    port: u16,
+    state: Arc<Mutex<State>>,
+}
+
+impl Server {
+    /// Start the server on a background thread.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let state = Arc::new(Mutex::new(State::default()));
+
+        let shared = Arc::clone(&state);
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = Arc::clone(&shared);
+                thread::spawn(move || handle(stream, &shared));
+            }
+        });
+
+        Server { port, state }
+    }
+
+    /// Absolute URL of a path on this server.
+    pub fn url(&self, path: &str) -> String {
+        format!("http://127.0.0.1:{}{}", self.port, path)
+    }
+
+    /// Register the responses served on a path.
+    pub fn route(&self, path: &str, responses: Vec<Response>) {
+        self.state
+            .lock()
+            .unwrap()
+            .routes
+            .insert(path.to_owned(), responses);
+    }
+
+    /// Register an HTML page linking to other paths of this server.
+    pub fn page(&self, path: &str, links: &[&str]) {
+        let body = links
+            .iter()
+            .map(|link| format!(r#"<a href="{}">link</a>"#, self.url(link)))
+            .collect::<String>();
+
+        self.route(
+            path,
+            vec![Response::html(&format!(
+                "<html><body>{}</body></html>",
+                body
+            ))],
+        );
+    }
+
+    /// Paths requested so far, in order.
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+
+    /// Highest number of requests handled at the same time.
+    pub fn max_concurrent(&self) -> usize {
+        self.state.lock().unwrap().max_active
+    }
+}
+
+/// PDF document with a page per text, an information dictionary and link
+/// annotations on its first page.
+pub fn pdf(pages: &[&str], info: &[(&str, &str)], links: &[&str]) -> Vec<u8> {
+    use lopdf::content::{Content, Operation};
+    use lopdf::{dictionary, Dictionary, Document, Object, Stream};
+
+    let mut document = Document::with_version("1.5");
+    let pages_id = document.new_object_id();
+    let font_id = document.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Courier",
+    });
+
+    let resources_id = document.add_object(dictionary! {
+        "Font" => dictionary! { "F1" => font_id },
+    });
+
+    let annotations: Vec<Object> = links
+        .iter()
+        .map(|link| {
+            document
+                .add_object(dictionary! {
+                    "Type" => "Annot",
+                    "Subtype" => "Link",
+                    "Rect" => vec![0.into(), 0.into(), 100.into(), 20.into()],
+                    "A" => dictionary! {
+                        "S" => "URI",
+                        "URI" => Object::string_literal(*link),
+                    },
+                })
+                .into()
+        })
+        .collect();
+
+    let mut kids: Vec<Object> = Vec::new();
+    for (index, text) in pages.iter().enumerate() {
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), 24.into()]),
+                Operation::new("Td", vec![100.into(), 600.into()]),
+                Operation::new("Tj", vec![Object::string_literal(*text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = document
+            .add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+
+        let mut page = dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+        };
+        if index == 0 {
+            page.set("Annots", annotations.clone());
+        }
+        kids.push(document.add_object(page).into());
+    }
+
+    let count = kids.len() as i64;
+    document.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => kids,
+            "Count" => count,
+            "Resources" => resources_id,
+            "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        }),
+    );
+    let catalog_id = document.add_object(dictionary! {
+        "Type" => "Catalog",
+        "Pages" => pages_id,
+    });
+    document.trailer.set("Root", catalog_id);
+
+    let mut information = Dictionary::new();
+    for (key, value) in info {
+        information.set(*key, lopdf::text_string(value));
+    }
+    let info_id = document.add_object(information);
+    document.trailer.set("Info", info_id);
+
+    let mut body = Vec::new();
+    document.save_to(&mut body).unwrap();
+    body
+}
+
+fn handle(stream: TcpStream, state: &Mutex<State>) {
+    let mut reader = BufReader::new(&stream);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+
+    // Skip headers.
+    loop {
+        let mut line = String::new();
+        match reader.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if line == "\r\n" => break,
+            Ok(_) => {},
+        }
+    }
+
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("/")
+        .to_owned();
+
+    let response = {
+        let mut state = state.lock().unwrap();
+        state.requests.push(path.clone());
+        state.active += 1;
+        state.max_active = state.max_active.max(state.active);
+
+        match state.routes.get_mut(&path) {
+            Some(responses) if responses.len() > 1 => responses.remove(0),
+            Some(responses) if !responses.is_empty() => responses[0].clone(),
+            _ => Response::status(404),
+        }
+    };
+
+    thread::sleep(response.delay);
+
+    let mut head = format!(
+        "HTTP/1.1 {} Status\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (key, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", key, value));
+    }
+    head.push_str("\r\n");
+
+    let mut stream = &stream;
+    let _ = stream.write_all(head.as_bytes());
+    let _ = stream.write_all(&response.body);
+    let _ = stream.flush();
+
+    state.lock().unwrap().active -= 1;
+}
@@ -0,0 +1,693 @@
+//! Visit of a single page.
+//!
+//! A [Visitor] holds everything needed to fetch a page and read it. It is
+//! shared by the concurrent visits of a crawl, while the frontier and the
+//! seen URLs stay with the [Crawler](crate::Crawler).
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use polymath_error::CrawlerError;
+use regex_lite::Regex;
+use scraper::Html;
+use tracing::{debug, error, warn};
+use ureq::Agent;
+use url::Url;
+
+use crate::extractor::link::{Link, LinkKind};
+use crate::extractor::meta::RobotsDirectives;
+use crate::normalize::Normalizer;
+use crate::page::FetchedPage;
+use crate::politeness::{Clock, Politeness};
+use crate::seen::Seen;
+use crate::summary::SkipReason;
+use crate::{
+    content, encoding, extractor, parse_url, retry, scheduler, Event, Task,
+};
+
+/// Values of the `rel` attribute asking crawlers not to follow a link.
+const NOFOLLOW_REL: [&str; 3] = ["nofollow", "ugc", "sponsored"];
+
+/// Maximum number of redirects followed for a request.
+pub(crate) const MAX_REDIRECTS: usize = 3;
+
+/// Result of a visit.
+#[derive(Debug)]
+pub(crate) enum Outcome {
+    /// The page was fetched.
+    Fetched {
+        /// Links to queue.
+        links: Vec<(Url, Task)>,
+        /// Links not followed, with their depth.
+        skipped: Vec<(Url, usize, SkipReason)>,
+        /// Canonical URL of the page, if it was not passed to the events as
+        /// a variant of it.
+        variant_of: Option<Url>,
+        /// Canonical URL under which the page was passed to the events, if
+        /// it is a variant of it.
+        indexed_as: Option<Url>,
+    },
+    /// The URL redirects to another one, to visit instead with this task.
+    Redirected { location: Url, task: Task },
+    /// The page was not fetched.
+    Skipped(SkipReason),
+    /// The page could not be crawled.
+    Failed(polymath_error::Error),
+}
+
+/// Response to a request.
+#[derive(Debug)]
+pub(crate) enum Response {
+    /// The response was read.
+    Page(Box<FetchedPage>),
+    /// The response media type is not allowed, its body was not read.
+    Unsupported { status: u16, content_type: String },
+    /// The response redirects to another URL, to request instead.
+    Redirect { status: u16, location: Url },
+}
+
+/// Settings and shared state of the visits of a crawl.
+#[derive(Debug)]
+pub(crate) struct Visitor {
+    pub(crate) agent: Agent,
+    pub(crate) allowed_domains: Vec<String>,
+    pub(crate) clock: Arc<dyn Clock>,
+    pub(crate) events: Vec<Arc<dyn Event>>,
+    pub(crate) extensions: Vec<String>,
+    pub(crate) follow_redirects: bool,
+    pub(crate) headers: HashMap<String, String>,
+    pub(crate) max_body_size: u64,
+    pub(crate) normalizer: Normalizer,
+    pub(crate) politeness: Arc<Mutex<Politeness>>,
+    pub(crate) respect_robots_meta: bool,
+    pub(crate) retry_after: Duration,
+    pub(crate) retry_count: usize,
+    pub(crate) seen: Arc<Mutex<Box<dyn Seen>>>,
+    pub(crate) skip_non_canonical: bool,
+}
+
+impl Visitor {
+    /// Fetch a page, pass it to the [events](Event) and return the links
+    /// found on it.
+    pub(crate) async fn visit(
+        self: Arc<Self>,
+        url: String,
+        task: Task,
+    ) -> Outcome {
+        match self.fetch(&url, task).await {
+            Outcome::Failed(error) => {
+                self.blocking(move |visitor| {
+                    visitor.notify_error(&url, &error);
+                    Outcome::Failed(error)
+                })
+                .await
+            },
+            outcome => outcome,
+        }
+    }
+
+    async fn fetch(self: &Arc<Self>, url: &str, task: Task) -> Outcome {
+        let url = match parse_url(url) {
+            Ok(url) => url,
+            Err(error) => return Outcome::Failed(error),
+        };
+        if let Some(extension) =
+            content::disallowed_extension(&url, &self.extensions)
+        {
+            debug!(
+                url = %url,
+                "Extension {} is not allowed, skipping.",
+                extension
+            );
+            return Outcome::Skipped(SkipReason::Extension(extension));
+        }
+
+        let crawl_delay = self
+            .blocking({
+                let url = url.clone();
+                move |visitor| {
+                    visitor.pre_process(url.as_str())?;
+                    Ok(visitor.crawl_delay(&url))
+                }
+            })
+            .await;
+        let crawl_delay = match crawl_delay {
+            Ok(crawl_delay) => crawl_delay,
+            Err(error) => return Outcome::Skipped(SkipReason::Rejected(error)),
+        };
+
+        let mut page = match self.request_with_retry(&url, crawl_delay).await {
+            Ok(Response::Page(page)) => *page,
+            Ok(Response::Redirect { location, .. }) => {
+                // Each hop is queued, so it goes through the same checks.
+                if task.redirects.len() == MAX_REDIRECTS {
+                    let first = task.redirects.first().unwrap_or(&url);
+                    return Outcome::Failed(too_many_redirects(first));
+                }
+                debug!(from = %url, to = %location, "Following redirect.");
+                let mut task = task;
+                task.redirects.push(url);
+                return Outcome::Redirected { location, task };
+            },
+            Ok(Response::Unsupported { content_type, .. }) => {
+                debug!(
+                    url = %url,
+                    "Content type {} is not allowed, skipping.",
+                    content_type
+                );
+                return Outcome::Skipped(SkipReason::ContentType(content_type));
+            },
+            Err(error) => return Outcome::Failed(error),
+        };
+        if let Some(first) = task.redirects.first() {
+            page.url = first.clone();
+            page.redirects = task.redirects.clone();
+        }
+        page.depth = task.depth;
+        page.referrer = task.referrer.as_deref().cloned();
+        page.link = task.link.as_deref().cloned();
+
+        self.blocking(move |visitor| visitor.post_process(page, task))
+            .await
+            .unwrap_or_else(Outcome::Failed)
+    }
+
+    /// Pass an error to the [events](Event).
+    fn notify_error(&self, url: &str, error: &polymath_error::Error) {
+        for event in &self.events {
+            event.on_error(url, error);
+        }
+    }
+
+    /// Run blocking code (network, events, parsing) outside of the async
+    /// runtime.
+    async fn blocking<F, R>(self: &Arc<Self>, f: F) -> R
+    where
+        F: FnOnce(&Visitor) -> R + Send + 'static,
+        R: Send + 'static,
+    {
+        let visitor = Arc::clone(self);
+
+        match tokio::task::spawn_blocking(move || f(&visitor)).await {
+            Ok(result) => result,
+            // Blocking tasks cannot be cancelled, they can only panic.
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
+    }
+
+    pub(crate) fn pre_process(
+        &self,
+        url: &str,
+    ) -> Result<(), polymath_error::Error> {
+        for event in &self.events {
+            event.before_request(url)?;
+        }
+
+        if !self.allowed_domains.is_empty() && !self.test_domain(url) {
+            return Err(polymath_error::Error::new(
+                polymath_error::ErrorType::Crawler(CrawlerError::InvalidDomain),
+                None,
+                Some(format!(
+                    "You have specified a domain limit ({:?}) and {} is not one of them.",
+                    self.allowed_domains, url
+                )),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Delay asked by the website between two requests, if any.
+    fn crawl_delay(&self, url: &Url) -> Option<Duration> {
+        self.events
+            .iter()
+            .find_map(|event| event.crawl_delay(url.as_str()))
+    }
+
+    /// Fetch a URL and read its body if its media type is allowed.
+    ///
+    /// Redirects are not followed but returned, for their target to go
+    /// through the same checks as any other URL.
+    pub(crate) fn request(
+        &self,
+        url: &Url,
+    ) -> Result<Response, polymath_error::Error> {
+        let start = Instant::now();
+        let request = self
+            .headers
+            .iter()
+            .fold(self.agent.get(url.as_str()), |req, (key, value)| {
+                req.set(key, value)
+            });
+
+        debug!("Fetch {} using the agent.", url);
+        let response = request.call().map_err(|e| {
+            polymath_error::Error::new(
+                polymath_error::ErrorType::Crawler(crawler_error(&e)),
+                Some(Box::new(e)),
+                Some(format!("while fetching {}", url)),
+            )
+        })?;
+
+        let status = response.status();
+        let location = response
+            .header("Location")
+            .filter(|_| self.follow_redirects && (300..400).contains(&status))
+            .and_then(|location| url.join(location).ok());
+        if let Some(location) = location {
+            return Ok(Response::Redirect { status, location });
+        }
+
+        let headers = response
+            .headers_names()
+            .into_iter()
+            .flat_map(|name| {
+                response
+                    .all(&name)
+                    .into_iter()
+                    .map(|value| (name.to_lowercase(), value.to_owned()))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        let content_type = response
+            .header("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .map(|media_type| media_type.trim().to_lowercase())
+            .filter(|media_type| !media_type.is_empty());
+
+        // Responses without media type are read, and sniffed later.
+        if let Some(media_type) = content_type.as_deref() {
+            if !content::is_allowed_type(media_type, url, &self.extensions) {
+                return Ok(Response::Unsupported {
+                    status,
+                    content_type: media_type.to_owned(),
+                });
+            }
+        }
+
+        let mut body = Vec::new();
+        response
+            .into_reader()
+            .take(self.max_body_size + 1)
+            .read_to_end(&mut body)
+            .map_err(|e| {
+                let error = if e.kind() == std::io::ErrorKind::TimedOut {
+                    CrawlerError::Timeout
+                } else {
+                    CrawlerError::NetworkError
+                };
+
+                polymath_error::Error::new(
+                    polymath_error::ErrorType::Crawler(error),
+                    Some(Box::new(e)),
+                    Some(format!("while reading {}", url)),
+                )
+            })?;
+        if body.len() as u64 > self.max_body_size {
+            return Err(polymath_error::Error::new(
+                polymath_error::ErrorType::Crawler(CrawlerError::BodyTooLarge),
+                None,
+                Some(format!("while reading {}", url)),
+            ));
+        }
+
+        let mut page = FetchedPage {
+            url: url.clone(),
+            final_url: url.clone(),
+            redirects: Vec::new(),
+            status,
+            headers,
+            content_type,
+            body,
+            encoding: None,
+            fetched_at: SystemTime::now(),
+            fetch_time: start.elapsed(),
+            depth: 0,
+            referrer: None,
+            link: None,
+            title: None,
+            meta: Vec::new(),
+            metadata: None,
+            canonical: None,
+            language_cluster: None,
+            language: None,
+            article: None,
+            structured_data: Vec::new(),
+            document: None,
+        };
+        if page.is_text() {
+            let tld =
+                page.final_url.domain().and_then(|d| d.rsplit('.').next());
+            let encoding = encoding::detect(
+                page.header("content-type"),
+                &page.body,
+                page.is_html(),
+                tld,
+            );
+            page.encoding = Some(encoding.name().to_owned());
+        }
+
+        Ok(Response::Page(Box::new(page)))
+    }
+
+    /// Send a request, and send it again while it fails with a retryable
+    /// error.
+    async fn request_with_retry(
+        self: &Arc<Self>,
+        url: &Url,
+        crawl_delay: Option<Duration>,
+    ) -> Result<Response, polymath_error::Error> {
+        let mut attempt = 0;
+
+        loop {
+            let response = self.polite_request(url, crawl_delay).await;
+
+            let error = match &response {
+                Err(error) if attempt < self.retry_count => error,
+                _ => return response,
+            };
+            if !error.is_retryable() {
+                return response;
+            }
+
+            let delay = match retry::retry_after(error) {
+                Some(delay) if delay > retry::MAX_BACKOFF => {
+                    warn!(url = %url, "Retry-After is too long, giving up.");
+                    return response;
+                },
+                Some(delay) => delay,
+                None => retry::backoff(self.retry_after, attempt as u32),
+            };
+
+            warn!(
+                url = %url,
+                attempt = attempt + 1,
+                "Request failed ({}), retrying in {:?}.",
+                error,
+                delay
+            );
+            self.clock.sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// Send a request once the host can be requested again.
+    async fn polite_request(
+        self: &Arc<Self>,
+        url: &Url,
+        crawl_delay: Option<Duration>,
+    ) -> Result<Response, polymath_error::Error> {
+        let host = scheduler::host(url);
+
+        let wait = {
+            let mut politeness = self.politeness.lock().unwrap();
+            politeness.set_crawl_delay(&host, crawl_delay);
+            politeness.reserve(&host, self.clock.now())
+        };
+        if !wait.is_zero() {
+            debug!(host, "Waiting {:?} before next request.", wait);
+            self.clock.sleep(wait).await;
+        }
+
+        let start = self.clock.now();
+        let response = self
+            .blocking({
+                let url = url.clone();
+                move |visitor| visitor.request(&url)
+            })
+            .await;
+        let status = match &response {
+            Ok(Response::Page(page)) => Some(page.status),
+            Ok(Response::Unsupported { status, .. }) => Some(*status),
+            Ok(Response::Redirect { status, .. }) => Some(*status),
+            Err(polymath_error::Error {
+                error_type:
+                    polymath_error::ErrorType::Crawler(CrawlerError::HttpStatus(
+                        status,
+                    )),
+                ..
+            }) => Some(*status),
+            Err(_) => None,
+        };
+        self.politeness.lock().unwrap().record(
+            &host,
+            start,
+            self.clock.now(),
+            status,
+        );
+
+        response
+    }
+
+    pub(crate) fn post_process(
+        &self,
+        mut page: FetchedPage,
+        task: Task,
+    ) -> Result<Outcome, polymath_error::Error> {
+        // Other resources are passed to the events as is, without links.
+        let mut discovered = if page.is_html() {
+            let document = Html::parse_document(&page.text());
+            let url = &page.final_url;
+            let meta = extractor::meta::meta_tags(&document)?;
+            let metadata = extractor::meta::metadata(&document, &meta, url)?;
+            page.article = extractor::article::article(&document, url);
+            page.structured_data =
+                extractor::structured::structured_data(&document, url)?;
+            let links = extractor::link::links(&document, url)?;
+            page.title = metadata.title.clone();
+            page.meta = meta;
+            page.metadata = Some(metadata);
+            links
+        } else if let Some(document) = extractor::extract_document(&page) {
+            // A broken document is still passed to the events, as is.
+            match document {
+                Ok(document) => {
+                    let links = document.links().to_vec();
+                    page.title = document.title().map(str::to_owned);
+                    page.document = Some(document);
+                    links
+                },
+                Err(error) => {
+                    warn!(
+                        url = %page.final_url,
+                        "Failed to read document: {}",
+                        error
+                    );
+                    self.notify_error(page.final_url.as_str(), &error);
+                    Vec::new()
+                },
+            }
+        } else {
+            Vec::new()
+        };
+
+        extractor::language::resolve(&mut page);
+
+        // Variants of a page are collapsed onto its canonical URL.
+        extractor::canonical::resolve(&mut page);
+        let canonical = page
+            .canonical
+            .clone()
+            .filter(|canonical| !self.is_canonical(canonical, &page));
+        let mut variant_of = None;
+        let mut indexed_as = None;
+        if let Some(canonical) = canonical {
+            if self.skip_non_canonical {
+                discovered.clear();
+                discovered.push(Link {
+                    url: canonical.clone(),
+                    kind: LinkKind::Resource,
+                    text: None,
+                    rel: vec!["canonical".to_owned()],
+                    title: None,
+                    caption: None,
+                });
+                variant_of = Some(canonical);
+            } else if self
+                .seen
+                .lock()
+                .unwrap()
+                .insert(self.normalizer.normalize(&canonical).as_str())
+            {
+                // The first variant crawled stands for its canonical page,
+                // which is not fetched anymore.
+                indexed_as = Some(canonical);
+            } else {
+                // The canonical page is already crawled, or queued.
+                variant_of = Some(canonical);
+            }
+        }
+
+        let directives = if self.respect_robots_meta {
+            RobotsDirectives::from_meta(&page.meta)
+        } else {
+            RobotsDirectives::default()
+        };
+        let url = &page.final_url;
+
+        if directives.noindex {
+            debug!(url = %url, "Page is marked noindex, skipping indexing.");
+        } else if let Some(canonical) = &variant_of {
+            debug!(
+                url = %url,
+                "Page is a variant of {}, skipping indexing.",
+                canonical
+            );
+        } else {
+            for event in &self.events {
+                if let Err(error) = event.after_request(&page) {
+                    warn!(url = %url, "Failed to process page: {}", error);
+                    self.notify_error(url.as_str(), &error);
+                }
+            }
+        }
+
+        let mut links = Vec::new();
+        let mut skipped = Vec::new();
+
+        if directives.nofollow {
+            debug!(url = %url, "Page is marked nofollow, skipping its links.");
+            return Ok(Outcome::Fetched {
+                links,
+                skipped,
+                variant_of,
+                indexed_as,
+            });
+        }
+
+        let depth = task.depth + 1;
+        let too_deep =
+            task.max_depth.is_some_and(|max_depth| depth > max_depth);
+        if too_deep {
+            debug!(url = %url, depth = task.depth, "Maximum depth reached.");
+        }
+
+        let referrer = Arc::new(url.clone());
+        for link in discovered {
+            let nofollow = link
+                .rel
+                .iter()
+                .any(|rel| NOFOLLOW_REL.contains(&rel.as_str()));
+
+            if self.respect_robots_meta && nofollow {
+                debug!(url = %link.url, "Link is marked nofollow, skipping.");
+                skipped.push((link.url, depth, SkipReason::NoFollow));
+            } else if too_deep {
+                skipped.push((link.url, depth, SkipReason::MaxDepth));
+            } else {
+                let found = link.url.clone();
+                let link = self.events.iter().try_fold(link, |link, event| {
+                    event.on_link_discovered(&page, link)
+                });
+                let Some(link) = link else {
+                    debug!(url = %found, "Link dropped by an event, skipping.");
+                    skipped.push((found, depth, SkipReason::Filtered));
+                    continue;
+                };
+
+                debug!("Found {} URL on {}", link.url, url);
+                let url = link.url.clone();
+                let task = Task {
+                    depth,
+                    referrer: Some(Arc::clone(&referrer)),
+                    link: Some(Arc::new(link)),
+                    redirects: Vec::new(),
+                    ..task
+                };
+                links.push((url, task));
+            }
+        }
+
+        Ok(Outcome::Fetched {
+            links,
+            skipped,
+            variant_of,
+            indexed_as,
+        })
+    }
+
+    /// Whether a canonical URL designates the page itself.
+    fn is_canonical(&self, canonical: &Url, page: &FetchedPage) -> bool {
+        let canonical = self.normalizer.normalize(canonical);
+
+        [&page.final_url, &page.url]
+            .into_iter()
+            .any(|url| self.normalizer.normalize(url) == canonical)
+    }
+
+    fn test_domain(&self, url: &str) -> bool {
+        url::Url::parse(url)
+            .map(|url| {
+                url.host_str()
+                    .map(|host| {
+                        self.allowed_domains.iter().any(|domain| {
+                            if let Ok(regex) = Regex::new(domain) {
+                                regex.is_match(host)
+                            } else {
+                                error!(
+                                    regex = domain,
+                                    "Regex is not a valid expression."
+                                );
+                                false
+                            }
+                        })
+                    })
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+    }
+}
+
+/// Error of a request redirected too many times, from `url`.
+pub(crate) fn too_many_redirects(url: &Url) -> polymath_error::Error {
+    polymath_error::Error::new(
+        polymath_error::ErrorType::Crawler(CrawlerError::TooManyRedirects),
+        None,
+        Some(format!("while fetching {}", url)),
+    )
+}
+
+/// Classify a failed request.
+fn crawler_error(error: &ureq::Error) -> CrawlerError {
+    let transport = match error {
+        ureq::Error::Status(status, _) => {
+            return CrawlerError::HttpStatus(*status)
+        },
+        ureq::Error::Transport(transport) => transport,
+    };
+
+    let timed_out = std::error::Error::source(transport)
+        .and_then(|source| source.downcast_ref::<std::io::Error>())
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::TimedOut);
+    if timed_out {
+        return CrawlerError::Timeout;
+    }
+
+    match transport.kind() {
+        ureq::ErrorKind::Dns => CrawlerError::Dns,
+        ureq::ErrorKind::TooManyRedirects => CrawlerError::TooManyRedirects,
+        // ureq reports handshake failures as connection failures, only
+        // their message tells them apart. It is the one of ureq 2 with
+        // rustls, "tls connection init failed": check it when upgrading.
+        ureq::ErrorKind::ConnectionFailed
+            if transport
+                .message()
+                .is_some_and(|message| message.starts_with("tls")) =>
+        {
+            CrawlerError::Tls
+        },
+        ureq::ErrorKind::ConnectionFailed
+        | ureq::ErrorKind::Io
+        | ureq::ErrorKind::ProxyConnect => CrawlerError::NetworkError,
+        ureq::ErrorKind::InvalidUrl
+        | ureq::ErrorKind::UnknownScheme
+        | ureq::ErrorKind::InsecureRequestHttpsOnly
+        | ureq::ErrorKind::BadStatus
+        | ureq::ErrorKind::BadHeader
+        | ureq::ErrorKind::InvalidProxyUrl
+        | ureq::ErrorKind::ProxyUnauthorized
+        | ureq::ErrorKind::HTTP => CrawlerError::InvalidRequest,
+    }
+}
@@ -60,6 +60,18 @@ impl fmt::Display for Error {
     }
 }
 
+impl Error {
+    /// Whether the operation may succeed if attempted again.
+    ///
+    /// Only [crawler errors](CrawlerError::is_retryable) can be retried.
+    pub fn is_retryable(&self) -> bool {
+        match self.error_type {
+            ErrorType::Crawler(ref error) => error.is_retryable(),
+            _ => false,
+        }
+    }
+}
+
 impl StdError for Error {}
 
 /// Defines the types of errors in Polymath.
@@ -130,6 +142,8 @@ impl StdError for DatabaseError {}
 pub enum ScraperError {
     /// [`scraper::error::SelectorErrorKind`] error.
     Selector,
+    /// The document is malformed or cannot be read.
+    InvalidDocument,
 }
 
 impl fmt::Display for ScraperError {
@@ -138,6 +152,9 @@ impl fmt::Display for ScraperError {
             ScraperError::Selector => {
                 write!(f, "Selector attribute is not valid.")
             },
+            ScraperError::InvalidDocument => {
+                write!(f, "The document could not be read.")
+            },
         }
     }
 }
@@ -145,7 +162,7 @@ impl fmt::Display for ScraperError {
 impl StdError for ScraperError {}
 
 /// Errors related to the `polymath-crawler`.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum CrawlerError {
     /// The domain is not allowed.
     InvalidDomain,
@@ -153,6 +170,39 @@ pub enum CrawlerError {
     NetworkError,
     /// The request body could not be read.
     ParseError,
+    /// The URL is disallowed by the robots.txt of the website.
+    DisallowedByRobots,
+    /// The server responded with an error status code.
+    HttpStatus(u16),
+    /// The host name could not be resolved.
+    Dns,
+    /// The TLS handshake failed.
+    Tls,
+    /// The server did not respond in time.
+    Timeout,
+    /// The request was redirected too many times.
+    TooManyRedirects,
+    /// The request could not be sent, such as a URL with an unsupported
+    /// scheme or an invalid header. Sending it again fails the same way.
+    InvalidRequest,
+    /// The response body exceeds the maximum allowed size.
+    BodyTooLarge,
+    /// The response content type is not handled by the crawler.
+    UnsupportedContentType,
+}
+
+impl CrawlerError {
+    /// Whether the request may succeed if sent again: timeouts, network
+    /// failures, server errors and `429 Too Many Requests`.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            CrawlerError::NetworkError | CrawlerError::Timeout => true,
+            CrawlerError::HttpStatus(status) => {
+                *status == 429 || *status >= 500
+            },
+            _ => false,
+        }
+    }
 }
 
 impl fmt::Display for CrawlerError {
@@ -167,6 +217,33 @@ impl fmt::Display for CrawlerError {
             CrawlerError::ParseError => {
                 write!(f, "The request body could not be read.")
             },
+            CrawlerError::DisallowedByRobots => {
+                write!(f, "The URL is disallowed by robots.txt.")
+            },
+            CrawlerError::HttpStatus(status) => {
+                write!(f, "The server responded with the status {}.", status)
+            },
+            CrawlerError::Dns => {
+                write!(f, "The host name could not be resolved.")
+            },
+            CrawlerError::Tls => {
+                write!(f, "The TLS handshake failed.")
+            },
+            CrawlerError::Timeout => {
+                write!(f, "The server did not respond in time.")
+            },
+            CrawlerError::TooManyRedirects => {
+                write!(f, "The request was redirected too many times.")
+            },
+            CrawlerError::InvalidRequest => {
+                write!(f, "The request could not be sent.")
+            },
+            CrawlerError::BodyTooLarge => {
+                write!(f, "The response body is too large.")
+            },
+            CrawlerError::UnsupportedContentType => {
+                write!(f, "The content type of the response is not supported.")
+            },
         }
     }
 }
@@ -174,6 +251,7 @@ impl fmt::Display for CrawlerError {
 impl StdError for CrawlerError {}
 
 #[cfg(test)]
+#[allow(clippy::io_other_error)]
 mod tests {
     use super::*;
 
@@ -213,6 +291,29 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_crawler_error_is_retryable() {
+        assert!(CrawlerError::Timeout.is_retryable());
+        assert!(CrawlerError::NetworkError.is_retryable());
+        assert!(CrawlerError::HttpStatus(503).is_retryable());
+        assert!(CrawlerError::HttpStatus(429).is_retryable());
+        assert!(!CrawlerError::HttpStatus(404).is_retryable());
+        assert!(!CrawlerError::Dns.is_retryable());
+        assert!(!CrawlerError::DisallowedByRobots.is_retryable());
+
+        let error = Error::new(
+            ErrorType::Crawler(CrawlerError::HttpStatus(502)),
+            None,
+            None,
+        );
+        assert!(error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "The server responded with the status 502."
+        );
+        assert!(!Error::new(ErrorType::Unspecified, None, None).is_retryable());
+    }
+
     #[test]
     fn test_error_with_cause() {
         let cause: BError = Box::new(std::io::Error::new(
@@ -1,14 +1,343 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
+#![forbid(unsafe_code)]
+#![deny(
+    unused_imports,
+    unused_mut,
+    missing_docs,
+    missing_debug_implementations
+)]
+//! Crawl frontier: the set of URLs waiting to be fetched.
+//!
+//! The [Frontier] decides in which order pages are visited:
+//! * [Strategy::BreadthFirst] visits every page of a level before going
+//!   deeper (default);
+//! * [Strategy::DepthFirst] follows the most recently discovered link first;
+//! * [Strategy::Priority] pops the entry with the highest priority first.
+//!
+//! # Examples
+//! ```rust
+//! use polymath_queue::{Frontier, Strategy};
+//!
+//! let mut frontier = Frontier::new(Strategy::BreadthFirst);
+//! frontier.push("https://gravitalia.com/".to_owned(), 0);
+//! frontier.push("https://gravitalia.com/about".to_owned(), 1);
+//!
+//! assert!(frontier.contains("https://gravitalia.com/about"));
+//! assert_eq!(
+//!     frontier.pop(),
+//!     Some(("https://gravitalia.com/".to_owned(), 0))
+//! );
+//! ```
+
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Order in which URLs leave the [Frontier].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// First in, first out.
+    #[default]
+    BreadthFirst,
+    /// Last in, first out.
+    DepthFirst,
+    /// Highest priority first. Entries sharing a priority are popped in
+    /// insertion order.
+    Priority,
+}
+
+/// Position of an entry in the frontier: the greatest rank leaves first.
+/// Ranks are unique, since each push gets a new sequence number.
+type Rank = (i64, i64);
+
+/// Entry stored in the heap of its group.
+#[derive(Debug)]
+struct Entry<T> {
+    rank: Rank,
+    url: String,
+    item: T,
+}
+
+impl<T> PartialEq for Entry<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl<T> Eq for Entry<T> {}
+
+impl<T> PartialOrd for Entry<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T> Ord for Entry<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Next entry of a group, in the heap of groups. It is stale once another
+/// entry of the group takes the first place.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct Head {
+    rank: Rank,
+    group: String,
+}
+
+/// Queue of URLs to crawl, each carrying an arbitrary `item`.
+///
+/// A URL can only be queued once at a time: pushing a URL already waiting
+/// in the frontier is a no-op.
+///
+/// URLs can be pushed in groups, such as the URLs of a host, which
+/// [pop_where](Frontier::pop_where) accepts or skips together. Popping
+/// only looks at the first entry of each group, so skipping a few busy
+/// groups stays cheap in large frontiers.
+#[derive(Debug)]
+pub struct Frontier<T> {
+    strategy: Strategy,
+    groups: HashMap<String, BinaryHeap<Entry<T>>>,
+    heads: BinaryHeap<Head>,
+    queued: HashSet<String>,
+    sequence: i64,
+}
+
+impl<T> Default for Frontier<T> {
+    fn default() -> Self {
+        Frontier::new(Strategy::default())
+    }
+}
+
+impl<T> Frontier<T> {
+    /// Create an empty [Frontier] using a specific [Strategy].
+    pub fn new(strategy: Strategy) -> Self {
+        Frontier {
+            strategy,
+            groups: HashMap::new(),
+            heads: BinaryHeap::new(),
+            queued: HashSet::new(),
+            sequence: 0,
+        }
+    }
+
+    /// Returns the [Strategy] used by the frontier.
+    pub fn strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    /// Number of URLs waiting in the frontier.
+    pub fn len(&self) -> usize {
+        self.queued.len()
+    }
+
+    /// Whether the frontier is empty.
+    pub fn is_empty(&self) -> bool {
+        self.queued.is_empty()
+    }
+
+    /// Whether a URL is currently waiting in the frontier.
+    pub fn contains(&self, url: &str) -> bool {
+        self.queued.contains(url)
+    }
+
+    /// Add a URL to the frontier with the default priority (0).
+    ///
+    /// Returns `false` if the URL was already queued.
+    pub fn push(&mut self, url: String, item: T) -> bool {
+        self.push_with_priority(url, item, 0)
+    }
+
+    /// Add a URL to the frontier with a specific priority.
+    ///
+    /// The priority is only taken into account by [Strategy::Priority].
+    /// Returns `false` if the URL was already queued.
+    pub fn push_with_priority(
+        &mut self,
+        url: String,
+        item: T,
+        priority: i64,
+    ) -> bool {
+        self.push_to_group(String::new(), url, item, priority)
+    }
+
+    /// Add a URL to a group of the frontier, such as the one of its host,
+    /// with a specific priority.
+    ///
+    /// The priority is only taken into account by [Strategy::Priority].
+    /// Returns `false` if the URL was already queued.
+    pub fn push_to_group(
+        &mut self,
+        group: String,
+        url: String,
+        item: T,
+        priority: i64,
+    ) -> bool {
+        if !self.queued.insert(url.clone()) {
+            return false;
+        }
+
+        self.sequence += 1;
+        let rank = match self.strategy {
+            Strategy::BreadthFirst => (0, -self.sequence),
+            Strategy::DepthFirst => (0, self.sequence),
+            Strategy::Priority => (priority, -self.sequence),
+        };
+
+        let entries = self.groups.entry(group.clone()).or_default();
+        if entries.peek().is_none_or(|first| first.rank < rank) {
+            self.heads.push(Head { rank, group });
+        }
+        entries.push(Entry { rank, url, item });
+
+        true
+    }
+
+    /// Remove the next URL to crawl from the frontier.
+    pub fn pop(&mut self) -> Option<(String, T)> {
+        self.pop_where(|_| true)
+    }
+
+    /// Remove the next URL whose group is accepted by `filter` from the
+    /// frontier. URLs of rejected groups stay in the frontier, at the same
+    /// place. URLs pushed without a group belong to the `""` group.
+    pub fn pop_where<F>(&mut self, mut filter: F) -> Option<(String, T)>
+    where
+        F: FnMut(&str) -> bool,
+    {
+        let mut rejected = Vec::new();
+        let mut found = None;
+
+        while let Some(head) = self.heads.pop() {
+            let Some(entries) = self.groups.get_mut(&head.group) else {
+                continue;
+            };
+            if entries.peek().map(|first| first.rank) != Some(head.rank) {
+                continue;
+            }
+            if !filter(&head.group) {
+                rejected.push(head);
+                continue;
+            }
+
+            let Some(entry) = entries.pop() else {
+                continue;
+            };
+            match entries.peek() {
+                Some(next) => self.heads.push(Head {
+                    rank: next.rank,
+                    group: head.group,
+                }),
+                None => {
+                    self.groups.remove(&head.group);
+                },
+            }
+            found = Some((entry.url, entry.item));
+            break;
+        }
+
+        self.heads.extend(rejected);
+        let (url, item) = found?;
+        self.queued.remove(&url);
+
+        Some((url, item))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn drain(frontier: &mut Frontier<()>) -> Vec<String> {
+        std::iter::from_fn(|| frontier.pop().map(|(url, _)| url)).collect()
+    }
+
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn test_breadth_first() {
+        let mut frontier = Frontier::new(Strategy::BreadthFirst);
+        frontier.push("a".to_owned(), ());
+        frontier.push("b".to_owned(), ());
+        frontier.push("c".to_owned(), ());
+
+        assert_eq!(frontier.len(), 3);
+        assert_eq!(drain(&mut frontier), vec!["a", "b", "c"]);
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    fn test_depth_first() {
+        let mut frontier = Frontier::new(Strategy::DepthFirst);
+        frontier.push("a".to_owned(), ());
+        frontier.push("b".to_owned(), ());
+        frontier.push("c".to_owned(), ());
+
+        assert_eq!(drain(&mut frontier), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut frontier = Frontier::new(Strategy::Priority);
+        frontier.push_with_priority("low".to_owned(), (), -1);
+        frontier.push_with_priority("first".to_owned(), (), 5);
+        frontier.push_with_priority("second".to_owned(), (), 5);
+        frontier.push("default".to_owned(), ());
+
+        assert_eq!(
+            drain(&mut frontier),
+            vec!["first", "second", "default", "low"]
+        );
+    }
+
+    #[test]
+    fn test_contains() {
+        let mut frontier = Frontier::default();
+        assert!(frontier.push("https://gravitalia.com/".to_owned(), ()));
+        assert!(!frontier.push("https://gravitalia.com/".to_owned(), ()));
+        assert!(frontier.contains("https://gravitalia.com/"));
+        assert_eq!(frontier.len(), 1);
+
+        frontier.pop();
+        assert!(!frontier.contains("https://gravitalia.com/"));
+        assert!(frontier.push("https://gravitalia.com/".to_owned(), ()));
+    }
+
+    #[test]
+    fn test_pop_where() {
+        for strategy in [
+            Strategy::BreadthFirst,
+            Strategy::DepthFirst,
+            Strategy::Priority,
+        ] {
+            let mut frontier = Frontier::new(strategy);
+            frontier.push_to_group("a".to_owned(), "a/1".to_owned(), (), 0);
+            frontier.push_to_group("b".to_owned(), "b/1".to_owned(), (), 0);
+            frontier.push_to_group("a".to_owned(), "a/2".to_owned(), (), 0);
+
+            let (url, _) = frontier.pop_where(|group| group == "b").unwrap();
+            assert_eq!(url, "b/1");
+            assert!(frontier.pop_where(|group| group == "b").is_none());
+
+            // Rejected URLs keep their order.
+            let expected = match strategy {
+                Strategy::DepthFirst => vec!["a/2", "a/1"],
+                _ => vec!["a/1", "a/2"],
+            };
+            assert_eq!(drain(&mut frontier), expected);
+        }
+    }
+
+    #[test]
+    fn test_groups_keep_global_order() {
+        let mut frontier = Frontier::new(Strategy::Priority);
+        frontier.push_to_group("a".to_owned(), "a/1".to_owned(), (), 1);
+        frontier.push_to_group("b".to_owned(), "b/1".to_owned(), (), 2);
+        frontier.push_to_group("a".to_owned(), "a/2".to_owned(), (), 3);
+        frontier.push_to_group("b".to_owned(), "b/2".to_owned(), (), 0);
+
+        assert_eq!(drain(&mut frontier), vec!["a/2", "b/1", "a/1", "b/2"]);
+        assert!(frontier.is_empty());
+
+        // Entries are still found after their group was emptied.
+        frontier.push_to_group("a".to_owned(), "a/3".to_owned(), (), 0);
+        assert_eq!(drain(&mut frontier), vec!["a/3"]);
     }
 }
@@ -0,0 +1,291 @@
+//! robots.txt enforcement for the [Crawler](polymath_crawler::Crawler).
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use polymath_cache::lru::LRUCache;
+use polymath_crawler::{Event, FetchedPage};
+use polymath_error::{CrawlerError, Error, ErrorType};
+use tracing::{debug, warn};
+use url::Url;
+
+use crate::RobotsTxt;
+
+/// Crawlers should not use a cached robots.txt for more than 24 hours.
+const MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Cached robots.txt of an origin.
+#[derive(Debug, Clone)]
+struct Entry {
+    robots: RobotsTxt,
+    expires_at: Instant,
+}
+
+/// [Event] rejecting URLs disallowed by the robots.txt of their website.
+///
+/// The robots.txt of each origin is fetched once and cached. Following
+/// [RFC 9309](https://www.rfc-editor.org/rfc/rfc9309.html#section-2.3.1):
+/// * a 4xx status (the file does not exist) allows every URL;
+/// * a 5xx status or a network error disallows every URL until
+///   [unreachable_delay](Extension::unreachable_delay) has elapsed.
+///
+/// # Examples
+/// ```rust
+/// use polymath_crawler::Crawler;
+/// use robots::Extension;
+///
+/// let crawler = Crawler::new()
+///     .user_agent("Gravitaliabot/1.0".to_owned())
+///     .register_event(Box::new(Extension::new("Gravitaliabot/1.0")));
+/// ```
+#[derive(Debug)]
+pub struct Extension {
+    cache: Mutex<LRUCache<String, Entry>>,
+    timeout: Duration,
+    unreachable_delay: Duration,
+    user_agent: String,
+}
+
+impl Extension {
+    /// Create an [Extension] obeying the rules written for `user_agent`.
+    ///
+    /// `user_agent` should be the one used by the crawler.
+    pub fn new(user_agent: &str) -> Self {
+        Extension {
+            cache: Mutex::new(LRUCache::with_capacity(1000)),
+            timeout: Duration::from_secs(10),
+            unreachable_delay: Duration::from_secs(60 * 60),
+            user_agent: user_agent.to_owned(),
+        }
+    }
+
+    /// Sets the number of robots.txt files kept in cache.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.cache = Mutex::new(LRUCache::with_capacity(capacity));
+        self
+    }
+
+    /// Sets the timeout used to fetch robots.txt files.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Sets how long a website is considered fully disallowed after its
+    /// robots.txt could not be reached.
+    pub fn unreachable_delay(mut self, delay: Duration) -> Self {
+        self.unreachable_delay = delay;
+        self
+    }
+
+    /// Returns the robots.txt applying to a URL, fetching it if it is not
+    /// cached yet.
+    pub fn robots_txt(&self, url: &Url) -> RobotsTxt {
+        self.with_robots_txt(url, RobotsTxt::clone)
+    }
+
+    /// Reads the robots.txt applying to a URL under the cache lock, fetching
+    /// it if it is not cached yet, without cloning it.
+    fn with_robots_txt<T>(
+        &self,
+        url: &Url,
+        read: impl FnOnce(&RobotsTxt) -> T,
+    ) -> T {
+        let origin = url.origin().ascii_serialization();
+
+        if let Some(entry) = self.cache.lock().unwrap().get(&origin) {
+            if entry.expires_at > Instant::now() {
+                return read(&entry.robots);
+            }
+        }
+
+        let entry = self.fetch(&origin);
+        let value = read(&entry.robots);
+        self.cache.lock().unwrap().put(origin, entry);
+
+        value
+    }
+
+    fn fetch(&self, origin: &str) -> Entry {
+        let url = format!("{}/robots.txt", origin);
+        debug!(url, "Fetching robots.txt.");
+
+        let agent = ureq::AgentBuilder::new()
+            .timeout(self.timeout)
+            .redirects(5)
+            .user_agent(&self.user_agent)
+            .build();
+
+        let unreachable = Entry {
+            robots: RobotsTxt::disallow_all(),
+            expires_at: Instant::now() + self.unreachable_delay,
+        };
+
+        match agent.get(&url).call() {
+            Ok(response) => match response.into_string() {
+                Ok(body) => Entry {
+                    robots: RobotsTxt::parse(&body),
+                    expires_at: Instant::now() + MAX_AGE,
+                },
+                Err(error) => {
+                    warn!(url, "Failed to read robots.txt: {}", error);
+                    unreachable
+                },
+            },
+            // Too many requests is handled like a server error.
+            Err(ureq::Error::Status(status, _))
+                if (400..500).contains(&status) && status != 429 =>
+            {
+                debug!(url, status, "No robots.txt, allowing every URL.");
+                Entry {
+                    robots: RobotsTxt::allow_all(),
+                    expires_at: Instant::now() + MAX_AGE,
+                }
+            },
+            Err(error) => {
+                warn!(url, "robots.txt is unreachable: {}", error);
+                unreachable
+            },
+        }
+    }
+}
+
+impl Event for Extension {
+    fn before_request(&self, url: &str) -> Result<(), Error> {
+        let parsed = Url::parse(url).map_err(|e| {
+            Error::new(
+                ErrorType::Crawler(CrawlerError::ParseError),
+                Some(Box::new(e)),
+                Some(format!("{} is not a valid URL", url)),
+            )
+        })?;
+
+        if self.with_robots_txt(&parsed, |robots| {
+            robots.is_allowed(&self.user_agent, url)
+        }) {
+            Ok(())
+        } else {
+            Err(Error::new(
+                ErrorType::Crawler(CrawlerError::DisallowedByRobots),
+                None,
+                Some(format!("{} is disallowed by robots.txt", url)),
+            ))
+        }
+    }
+
+    fn after_request(&self, _page: &FetchedPage) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn crawl_delay(&self, url: &str) -> Option<Duration> {
+        let url = Url::parse(url).ok()?;
+        self.with_robots_txt(&url, |robots| {
+            robots.crawl_delay(&self.user_agent)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    use super::*;
+
+    /// Serve `robots.txt` with a status and a body, and count requests.
+    fn serve(status: u16, body: &'static str) -> (String, Arc<Mutex<usize>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let origin = format!("http://{}", listener.local_addr().unwrap());
+        let count = Arc::new(Mutex::new(0));
+
+        let requests = Arc::clone(&count);
+        thread::spawn(move || {
+            for mut stream in listener.incoming().flatten() {
+                let mut reader = BufReader::new(&stream);
+                let mut line = String::new();
+                while reader.read_line(&mut line).is_ok_and(|n| n > 2) {
+                    line.clear();
+                }
+
+                *requests.lock().unwrap() += 1;
+                let _ = write!(
+                    stream,
+                    "HTTP/1.1 {} Status\r\nContent-Length: {}\r\n\
+                     Connection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+            }
+        });
+
+        (origin, count)
+    }
+
+    fn is_disallowed(result: Result<(), Error>) -> bool {
+        matches!(
+            result,
+            Err(Error {
+                error_type: ErrorType::Crawler(
+                    CrawlerError::DisallowedByRobots
+                ),
+                ..
+            })
+        )
+    }
+
+    #[test]
+    fn test_rules_are_enforced() {
+        let (origin, count) =
+            serve(200, "User-agent: polymath\nDisallow: /private/");
+        let extension = Extension::new("polymath/0.1.0");
+
+        assert!(extension
+            .before_request(&format!("{}/public/", origin))
+            .is_ok());
+        assert!(is_disallowed(
+            extension.before_request(&format!("{}/private/a", origin))
+        ));
+        // robots.txt is cached.
+        assert_eq!(*count.lock().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_crawl_delay() {
+        let (origin, _) = serve(200, "User-agent: *\nCrawl-delay: 4");
+        let extension = Extension::new("polymath");
+
+        assert_eq!(
+            extension.crawl_delay(&format!("{}/", origin)),
+            Some(Duration::from_secs(4))
+        );
+    }
+
+    #[test]
+    fn test_missing_robots_txt() {
+        let (origin, _) = serve(404, "");
+        let extension = Extension::new("polymath");
+
+        assert!(extension
+            .before_request(&format!("{}/private/", origin))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_unreachable_robots_txt() {
+        let (origin, count) = serve(503, "");
+        let extension =
+            Extension::new("polymath").unreachable_delay(Duration::ZERO);
+
+        assert!(is_disallowed(
+            extension.before_request(&format!("{}/", origin))
+        ));
+        // The delay is over: robots.txt is fetched again.
+        assert!(is_disallowed(
+            extension.before_request(&format!("{}/", origin))
+        ));
+        assert_eq!(*count.lock().unwrap(), 2);
+    }
+}
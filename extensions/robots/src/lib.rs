@@ -1,14 +1,20 @@
-pub fn add(left: usize, right: usize) -> usize {
-    left + right
-}
+#![forbid(unsafe_code)]
+#![deny(
+    dead_code,
+    unused_imports,
+    unused_mut,
+    missing_docs,
+    missing_debug_implementations
+)]
+//! manage robots.txt restrictions.
+//!
+//! [RobotsTxt] parses a robots.txt file as described by
+//! [RFC 9309](https://www.rfc-editor.org/rfc/rfc9309.html) and tells
+//! whether a crawler may fetch a URL. [Extension] enforces those rules on
+//! every request made by a [Crawler](polymath_crawler::Crawler).
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod extension;
+mod parser;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use extension::Extension;
+pub use parser::RobotsTxt;
@@ -0,0 +1,495 @@
+//! robots.txt parser and matcher following
+//! [RFC 9309](https://www.rfc-editor.org/rfc/rfc9309.html).
+
+use std::time::Duration;
+
+use url::Url;
+
+/// Crawlers should parse at least 500 KiB of a robots.txt file.
+const MAX_SIZE: usize = 500 * 1024;
+
+/// Rule of a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    allow: bool,
+    pattern: String,
+}
+
+impl Rule {
+    /// Whether the rule matches a path, and with which priority.
+    ///
+    /// The priority is the length of the pattern: the most specific rule
+    /// wins.
+    fn matches(&self, path: &str) -> Option<usize> {
+        matches(&self.pattern, path).then_some(self.pattern.len())
+    }
+}
+
+/// Group of rules applying to one or more user-agents.
+#[derive(Debug, Clone, Default, PartialEq)]
+struct Group {
+    user_agents: Vec<String>,
+    rules: Vec<Rule>,
+    crawl_delay: Option<Duration>,
+}
+
+/// Parsed robots.txt file.
+///
+/// # Examples
+/// ```rust
+/// use robots::RobotsTxt;
+///
+/// let robots = RobotsTxt::parse(
+///     "User-agent: *\nDisallow: /private/\nAllow: /private/public.html",
+/// );
+///
+/// assert!(robots.is_allowed("polymath", "https://example.com/"));
+/// assert!(!robots.is_allowed("polymath", "https://example.com/private/a"));
+/// assert!(robots.is_allowed("polymath", "/private/public.html"));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RobotsTxt {
+    groups: Vec<Group>,
+    sitemaps: Vec<String>,
+}
+
+impl RobotsTxt {
+    /// Parse the content of a robots.txt file.
+    ///
+    /// Parsing never fails: invalid lines are ignored.
+    pub fn parse(content: &str) -> Self {
+        let mut content = content.strip_prefix('\u{feff}').unwrap_or(content);
+        if content.len() > MAX_SIZE {
+            let mut end = MAX_SIZE;
+            while !content.is_char_boundary(end) {
+                end -= 1;
+            }
+            content = &content[..end];
+        }
+
+        let mut robots = RobotsTxt::default();
+        let mut group: Option<Group> = None;
+        // Whether the last line of the current group was a user-agent.
+        let mut in_agents = false;
+
+        for line in content.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            match key.trim().to_lowercase().as_str() {
+                "user-agent" => {
+                    if !in_agents {
+                        robots.groups.extend(group.take());
+                    }
+
+                    group
+                        .get_or_insert_with(Group::default)
+                        .user_agents
+                        .push(value.to_lowercase());
+                    in_agents = true;
+                },
+                key @ ("allow" | "disallow") => {
+                    in_agents = false;
+
+                    // Rules outside of a group, or without a path, are
+                    // ignored.
+                    if let Some(group) =
+                        group.as_mut().filter(|_| !value.is_empty())
+                    {
+                        group.rules.push(Rule {
+                            allow: key == "allow",
+                            pattern: encode(value),
+                        });
+                    }
+                },
+                "crawl-delay" => {
+                    in_agents = false;
+
+                    // Negative, infinite or too large delays are ignored.
+                    if let (Some(group), Ok(delay)) =
+                        (&mut group, value.parse::<f64>())
+                    {
+                        if let Ok(delay) = Duration::try_from_secs_f64(delay) {
+                            group.crawl_delay = Some(delay);
+                        }
+                    }
+                },
+                "sitemap" if !value.is_empty() => {
+                    robots.sitemaps.push(value.to_owned());
+                },
+                _ => {},
+            }
+        }
+
+        robots.groups.extend(group);
+        robots
+    }
+
+    /// robots.txt allowing every URL, used when the file is unavailable
+    /// (4xx status).
+    pub fn allow_all() -> Self {
+        Self::default()
+    }
+
+    /// robots.txt disallowing every URL, used when the file is unreachable
+    /// (5xx status or network error).
+    pub fn disallow_all() -> Self {
+        RobotsTxt {
+            groups: vec![Group {
+                user_agents: vec!["*".to_owned()],
+                rules: vec![Rule {
+                    allow: false,
+                    pattern: "/".to_owned(),
+                }],
+                crawl_delay: None,
+            }],
+            sitemaps: Vec::new(),
+        }
+    }
+
+    /// Whether a user-agent may fetch a URL.
+    ///
+    /// `user_agent` is either the product token (`polymath`) or a full
+    /// user-agent string (`polymath/0.1.0`). `url` is either an absolute
+    /// URL or a path with an optional query (`/search?q=rust`).
+    pub fn is_allowed(&self, user_agent: &str, url: &str) -> bool {
+        let path = encode(&match Url::parse(url) {
+            Ok(url) => match url.query() {
+                Some(query) => format!("{}?{}", url.path(), query),
+                None => url.path().to_owned(),
+            },
+            Err(_) => url.to_owned(),
+        });
+
+        // The robots.txt file itself is always allowed.
+        if path == "/robots.txt" {
+            return true;
+        }
+
+        self.rules(user_agent)
+            .filter_map(|rule| rule.matches(&path).map(|len| (len, rule.allow)))
+            // On equal length, allow wins.
+            .max()
+            .is_none_or(|(_, allow)| allow)
+    }
+
+    /// Delay between two requests asked to a user-agent, if any.
+    pub fn crawl_delay(&self, user_agent: &str) -> Option<Duration> {
+        self.groups_for(user_agent)
+            .into_iter()
+            .find_map(|group| group.crawl_delay)
+    }
+
+    /// Sitemaps URLs listed in the file.
+    pub fn sitemaps(&self) -> &[String] {
+        &self.sitemaps
+    }
+
+    /// Rules applying to a user-agent.
+    fn rules(&self, user_agent: &str) -> impl Iterator<Item = &Rule> {
+        self.groups_for(user_agent)
+            .into_iter()
+            .flat_map(|group| group.rules.iter())
+    }
+
+    /// Groups applying to a user-agent: every group naming its product
+    /// token, or the `*` groups if there is none.
+    fn groups_for(&self, user_agent: &str) -> Vec<&Group> {
+        let token = product_token(user_agent);
+
+        let groups = self
+            .groups
+            .iter()
+            .filter(|group| {
+                group.user_agents.iter().any(|agent| {
+                    !token.is_empty() && product_token(agent) == token
+                })
+            })
+            .collect::<Vec<_>>();
+
+        if !groups.is_empty() {
+            return groups;
+        }
+
+        self.groups
+            .iter()
+            .filter(|group| group.user_agents.iter().any(|agent| agent == "*"))
+            .collect()
+    }
+}
+
+/// Product token of a user-agent: its leading letters, underscores and
+/// hyphens, lowercased.
+fn product_token(user_agent: &str) -> String {
+    user_agent
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic() || *c == '_' || *c == '-')
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Percent-encode non-ASCII characters and uppercase existing
+/// percent-encodings, so that paths and patterns compare equal.
+fn encode(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    let mut chars = path.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c == '%' {
+            encoded.push('%');
+            for _ in 0..2 {
+                if let Some(hex) = chars.next_if(char::is_ascii_hexdigit) {
+                    encoded.push(hex.to_ascii_uppercase());
+                }
+            }
+        } else if c.is_ascii() {
+            encoded.push(c);
+        } else {
+            let mut buffer = [0; 4];
+            for byte in c.encode_utf8(&mut buffer).bytes() {
+                encoded.push_str(&format!("%{:02X}", byte));
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Whether a pattern matches the beginning of a path.
+///
+/// `*` matches any sequence of characters, and a trailing `$` anchors the
+/// pattern to the end of the path.
+fn matches(pattern: &str, path: &str) -> bool {
+    let (pattern, anchored) = match pattern.strip_suffix('$') {
+        Some(pattern) => (pattern, true),
+        None => (pattern, false),
+    };
+
+    let mut pieces = pattern.split('*');
+    let first = pieces.next().unwrap_or_default();
+    if !path.starts_with(first) {
+        return false;
+    }
+
+    let mut position = first.len();
+    let pieces = pieces.collect::<Vec<_>>();
+
+    let Some((last, middle)) = pieces.split_last() else {
+        // No wildcard.
+        return !anchored || path.len() == first.len();
+    };
+
+    for piece in middle {
+        match path[position..].find(piece) {
+            Some(index) => position += index + piece.len(),
+            None => return false,
+        }
+    }
+
+    if anchored {
+        path.len() >= position + last.len() && path.ends_with(last)
+    } else {
+        path[position..].contains(last)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Example of
+    /// [RFC 9309, section 5.1](https://www.rfc-editor.org/rfc/rfc9309.html#section-5.1).
+    const RFC_EXAMPLE: &str = "User-Agent: *
+Disallow: *.gif$
+Disallow: /example/
+Allow: /publications/
+
+User-Agent: foobot
+Disallow:/
+Allow:/example/page.html
+Allow:/example/allowed.gif
+
+User-Agent: barbot
+User-Agent: bazbot
+Disallow: /example/page.html
+
+User-Agent: quxbot
+
+EOF";
+
+    #[test]
+    fn test_rfc_user_agents() {
+        let robots = RobotsTxt::parse(RFC_EXAMPLE);
+
+        // Unknown crawlers use the `*` group.
+        assert!(robots.is_allowed("polymath", "/publications/"));
+        assert!(!robots.is_allowed("polymath", "/example/page.html"));
+        assert!(!robots.is_allowed("polymath", "/image.gif"));
+        assert!(robots.is_allowed("polymath", "/image.gif?size=2"));
+
+        // foobot only has access to two URLs.
+        assert!(!robots.is_allowed("foobot", "/"));
+        assert!(!robots.is_allowed("FooBot/2.1", "/publications/"));
+        assert!(robots.is_allowed("foobot", "/example/page.html"));
+        assert!(robots.is_allowed("foobot", "/example/allowed.gif"));
+
+        // barbot and bazbot share a group.
+        for agent in ["barbot", "bazbot"] {
+            assert!(!robots.is_allowed(agent, "/example/page.html"));
+            assert!(robots.is_allowed(agent, "/example/"));
+            assert!(robots.is_allowed(agent, "/image.gif"));
+        }
+
+        // quxbot group has no rule.
+        assert!(robots.is_allowed("quxbot", "/example/page.html"));
+    }
+
+    #[test]
+    fn test_rfc_longest_match() {
+        // RFC 9309, section 5.2.
+        let robots = RobotsTxt::parse(
+            "User-Agent: foobot
+Allow: /example/page/
+Disallow: /example/page/disallowed.gif",
+        );
+
+        assert!(robots.is_allowed("foobot", "/example/page/"));
+        assert!(robots.is_allowed("foobot", "/example/page/allowed.gif"));
+        assert!(!robots.is_allowed("foobot", "/example/page/disallowed.gif"));
+    }
+
+    #[test]
+    fn test_equivalent_rules() {
+        let robots = RobotsTxt::parse(
+            "User-agent: *
+Disallow: /page
+Allow: /page",
+        );
+
+        assert!(robots.is_allowed("polymath", "/page"));
+    }
+
+    #[test]
+    fn test_special_characters() {
+        // RFC 9309, section 2.2.3.
+        let robots = RobotsTxt::parse(
+            "User-agent: *
+Disallow: /path/file-with-a-*.html
+Disallow: /this/path/exactly$
+Disallow: /foo/bar/ツ
+Disallow: /qux/%62%61%7a
+Disallow: /search?q=",
+        );
+
+        assert!(!robots.is_allowed("polymath", "/path/file-with-a-dog.html"));
+        assert!(!robots.is_allowed("polymath", "/path/file-with-a-.html"));
+        assert!(robots.is_allowed("polymath", "/path/file-with-a-dog.htm"));
+
+        assert!(!robots.is_allowed("polymath", "/this/path/exactly"));
+        assert!(robots.is_allowed("polymath", "/this/path/exactly/"));
+        assert!(robots.is_allowed("polymath", "/this/path/exactly?a=1"));
+
+        assert!(!robots.is_allowed("polymath", "/foo/bar/%E3%83%84"));
+        assert!(!robots.is_allowed("polymath", "/foo/bar/ツ"));
+        assert!(
+            !robots.is_allowed("polymath", "https://example.com/foo/bar/ツ")
+        );
+
+        assert!(!robots.is_allowed("polymath", "/qux/%62%61%7A"));
+        assert!(robots.is_allowed("polymath", "/qux/baz"));
+
+        assert!(!robots.is_allowed("polymath", "/search?q=rust"));
+        assert!(robots.is_allowed("polymath", "/search"));
+    }
+
+    #[test]
+    fn test_groups_are_merged() {
+        let robots = RobotsTxt::parse(
+            "User-agent: polymath
+Disallow: /a
+
+User-agent: *
+Disallow: /
+
+User-agent: Polymath
+Disallow: /b",
+        );
+
+        assert!(!robots.is_allowed("polymath", "/a"));
+        assert!(!robots.is_allowed("polymath", "/b"));
+        assert!(robots.is_allowed("polymath", "/c"));
+        assert!(!robots.is_allowed("otherbot", "/c"));
+    }
+
+    #[test]
+    fn test_robots_txt_is_allowed() {
+        let robots = RobotsTxt::disallow_all();
+
+        assert!(!robots.is_allowed("polymath", "https://example.com/"));
+        assert!(robots.is_allowed("polymath", "https://example.com/robots.txt"));
+        assert!(RobotsTxt::allow_all().is_allowed("polymath", "/"));
+    }
+
+    #[test]
+    fn test_crawl_delay_and_sitemaps() {
+        let robots = RobotsTxt::parse(
+            "\u{feff}# Rules for everyone.
+Sitemap: https://example.com/sitemap.xml
+user-agent: *
+crawl-delay: 1.5 # seconds
+disallow:
+
+User-agent: slowbot
+Crawl-delay: 30
+
+SITEMAP: https://example.com/news.xml",
+        );
+
+        assert_eq!(
+            robots.crawl_delay("polymath"),
+            Some(Duration::from_millis(1500))
+        );
+        assert_eq!(
+            robots.crawl_delay("slowbot"),
+            Some(Duration::from_secs(30))
+        );
+        assert!(robots.is_allowed("polymath", "/"));
+        assert_eq!(
+            robots.sitemaps(),
+            &[
+                "https://example.com/sitemap.xml",
+                "https://example.com/news.xml"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_invalid_crawl_delays() {
+        for delay in ["1e300", "-1", "inf", "NaN", "soon"] {
+            let robots = RobotsTxt::parse(&format!(
+                "User-agent: *\nCrawl-delay: {}",
+                delay
+            ));
+            assert_eq!(robots.crawl_delay("polymath"), None);
+        }
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let robots = RobotsTxt::parse(
+            "Disallow: /before-any-group
+User-agent: *
+This line is invalid
+Disalow: /typo
+Disallow: /private",
+        );
+
+        assert!(robots.is_allowed("polymath", "/before-any-group"));
+        assert!(robots.is_allowed("polymath", "/typo"));
+        assert!(!robots.is_allowed("polymath", "/private"));
+    }
+}